@@ -0,0 +1,362 @@
+pub mod animation {
+    use std::{
+        collections::HashMap,
+        ops::{Add, Mul, Sub},
+    };
+
+    use crate::{matrix::matrix::Vec3, scene::scene::SceneGraph, shapes::shapes::Transformation};
+
+    /// Something holding named transformations, such as scene nodes or skeleton joints,
+    /// which clips can animate
+    pub trait AnimationTarget {
+        fn target_mut(&mut self, name: &str) -> Option<&mut Transformation>;
+
+        /// The morph target weights of the mesh called `name`, if it has any
+        fn morph_weights_mut(&mut self, _name: &str) -> Option<&mut [f32]> {
+            None
+        }
+    }
+
+    impl AnimationTarget for SceneGraph {
+        fn target_mut(&mut self, name: &str) -> Option<&mut Transformation> {
+            let id = self.find(name)?;
+            Some(&mut self.get_mut(id)?.transformation)
+        }
+
+        fn morph_weights_mut(&mut self, name: &str) -> Option<&mut [f32]> {
+            let id = self.find(name)?;
+            Some(self.get_mut(id)?.mesh.as_mut()?.morph_weights_mut())
+        }
+    }
+
+    /// Anything which can be interpolated between keyframes
+    pub trait Animatable:
+        Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self>
+    {
+    }
+    impl<T> Animatable for T where T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T> {}
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Interpolation {
+        /// Hold each keyframe's value until the next one
+        Step,
+        Linear,
+        /// Cubic Hermite spline through the keyframes, with Catmull-Rom tangents
+        Cubic,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Keyframe<T: Animatable> {
+        pub time: f32,
+        pub value: T,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Track<T: Animatable> {
+        keyframes: Vec<Keyframe<T>>,
+        interpolation: Interpolation,
+    }
+
+    impl<T: Animatable> Track<T> {
+        /// `keyframes` are `(time, value)` pairs, in any order
+        pub fn new(interpolation: Interpolation, keyframes: Vec<(f32, T)>) -> Self {
+            let mut keyframes: Vec<_> = keyframes
+                .into_iter()
+                .map(|(time, value)| Keyframe { time, value })
+                .collect();
+            keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+            Self {
+                keyframes,
+                interpolation,
+            }
+        }
+
+        pub fn keyframes(&self) -> &[Keyframe<T>] {
+            &self.keyframes
+        }
+
+        /// The time of the last keyframe
+        pub fn duration(&self) -> f32 {
+            self.keyframes.last().map_or(0.0, |x| x.time)
+        }
+
+        /// The value at `time`, holding the first and last values outside the track.
+        /// Returns `None` if the track has no keyframes.
+        pub fn sample(&self, time: f32) -> Option<T> {
+            let keys = &self.keyframes;
+            let next = keys.partition_point(|x| x.time <= time);
+            if next == 0 {
+                return keys.first().map(|x| x.value);
+            } else if next == keys.len() {
+                return keys.last().map(|x| x.value);
+            }
+            let (a, b) = (keys[next - 1], keys[next]);
+            let span = b.time - a.time;
+            let t = (time - a.time) / span;
+
+            Some(match self.interpolation {
+                Interpolation::Step => a.value,
+                Interpolation::Linear => a.value + (b.value - a.value) * t,
+                Interpolation::Cubic => {
+                    let tangent_a = self.tangent(next - 1) * span;
+                    let tangent_b = self.tangent(next) * span;
+                    let (t2, t3) = (t * t, t * t * t);
+                    a.value * (2.0 * t3 - 3.0 * t2 + 1.0)
+                        + tangent_a * (t3 - 2.0 * t2 + t)
+                        + b.value * (-2.0 * t3 + 3.0 * t2)
+                        + tangent_b * (t3 - t2)
+                }
+            })
+        }
+
+        /// Rate of change at a keyframe, from its neighbours.
+        /// Neighbours at the same time make a jump rather than a slope, so that's flat.
+        fn tangent(&self, index: usize) -> T {
+            let keys = &self.keyframes;
+            let before = keys[index.saturating_sub(1)];
+            let after = keys[(index + 1).min(keys.len() - 1)];
+            let gap = after.time - before.time;
+            if gap > 0.0 {
+                (after.value - before.value) * (1.0 / gap)
+            } else {
+                (after.value - before.value) * 0.0
+            }
+        }
+    }
+
+    /// Tracks animating each part of one `Transformation`, and the morph target weights of
+    /// its mesh. Missing tracks leave that part of the transformation alone.
+    #[derive(Clone, Debug, Default)]
+    pub struct Channel {
+        pub translation: Option<Track<Vec3>>,
+        pub rotation: Option<Track<Vec3>>,
+        pub scale: Option<Track<Vec3>>,
+        /// One track per morph target, in the same order as the mesh's targets
+        pub weights: Vec<Track<f32>>,
+    }
+
+    impl Channel {
+        pub fn duration(&self) -> f32 {
+            [&self.translation, &self.rotation, &self.scale]
+                .iter()
+                .filter_map(|x| x.as_ref())
+                .map(|x| x.duration())
+                .chain(self.weights.iter().map(|x| x.duration()))
+                .fold(0.0, f32::max)
+        }
+
+        pub fn apply_weights(&self, time: f32, weights: &mut [f32]) {
+            for (track, weight) in self.weights.iter().zip(weights.iter_mut()) {
+                if let Some(sample) = track.sample(time) {
+                    *weight = sample;
+                }
+            }
+        }
+
+        pub fn apply(&self, time: f32, transformation: &mut Transformation) {
+            let parts = [
+                (&self.translation, &mut transformation.translation),
+                (&self.rotation, &mut transformation.rotation),
+                (&self.scale, &mut transformation.scale),
+            ];
+            for (track, value) in parts {
+                if let Some(sample) = track.as_ref().and_then(|x| x.sample(time)) {
+                    *value = sample;
+                }
+            }
+        }
+    }
+
+    /// A set of channels, each driving the transformation with the channel's name
+    #[derive(Clone, Debug, Default)]
+    pub struct Clip {
+        channels: HashMap<String, Channel>,
+    }
+
+    impl Clip {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn with_channel(mut self, target: &str, channel: Channel) -> Self {
+            self.channels.insert(target.to_string(), channel);
+            self
+        }
+
+        pub fn channel(&self, target: &str) -> Option<&Channel> {
+            self.channels.get(target)
+        }
+
+        pub fn channels(&self) -> impl Iterator<Item = (&str, &Channel)> {
+            self.channels.iter().map(|(name, x)| (name.as_str(), x))
+        }
+
+        /// The time of the last keyframe in any channel
+        pub fn duration(&self) -> f32 {
+            self.channels
+                .values()
+                .map(|x| x.duration())
+                .fold(0.0, f32::max)
+        }
+
+        /// Applies the `target` channel, if there is one
+        pub fn apply(&self, target: &str, time: f32, transformation: &mut Transformation) {
+            if let Some(channel) = self.channels.get(target) {
+                channel.apply(time, transformation);
+            }
+        }
+
+        pub fn apply_weights(&self, target: &str, time: f32, weights: &mut [f32]) {
+            if let Some(channel) = self.channels.get(target) {
+                channel.apply_weights(time, weights);
+            }
+        }
+
+        /// Applies each channel to the scene node or joint with the same name
+        pub fn apply_to(&self, time: f32, target: &mut impl AnimationTarget) {
+            for (name, channel) in self.channels.iter() {
+                if let Some(transformation) = target.target_mut(name) {
+                    channel.apply(time, transformation);
+                }
+                if let Some(weights) = target.morph_weights_mut(name) {
+                    channel.apply_weights(time, weights);
+                }
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum PlaybackMode {
+        /// Play once and stop on the last frame
+        Once,
+        Loop,
+        /// Play forwards, then backwards, then forwards again...
+        PingPong,
+    }
+
+    /// Plays a clip over time
+    #[derive(Clone, Debug)]
+    pub struct AnimationPlayer {
+        clip: Clip,
+        mode: PlaybackMode,
+        pub speed: f32,
+        elapsed: f32,
+    }
+
+    impl AnimationPlayer {
+        pub fn new(clip: Clip, mode: PlaybackMode) -> Self {
+            Self {
+                clip,
+                mode,
+                speed: 1.0,
+                elapsed: 0.0,
+            }
+        }
+
+        pub fn clip(&self) -> &Clip {
+            &self.clip
+        }
+
+        pub fn advance(&mut self, dt: f32) {
+            self.elapsed += dt * self.speed;
+        }
+
+        pub fn restart(&mut self) {
+            self.elapsed = 0.0;
+        }
+
+        /// The position within the clip, after looping or bouncing
+        pub fn time(&self) -> f32 {
+            let duration = self.clip.duration();
+            if duration <= 0.0 {
+                return 0.0;
+            }
+            match self.mode {
+                PlaybackMode::Once => self.elapsed.clamp(0.0, duration),
+                PlaybackMode::Loop => self.elapsed.rem_euclid(duration),
+                PlaybackMode::PingPong => {
+                    let time = self.elapsed.rem_euclid(2.0 * duration);
+                    if time > duration {
+                        2.0 * duration - time
+                    } else {
+                        time
+                    }
+                }
+            }
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.mode == PlaybackMode::Once && self.elapsed >= self.clip.duration()
+        }
+
+        pub fn apply(&self, target: &str, transformation: &mut Transformation) {
+            self.clip.apply(target, self.time(), transformation);
+        }
+
+        pub fn apply_weights(&self, target: &str, weights: &mut [f32]) {
+            self.clip.apply_weights(target, self.time(), weights);
+        }
+
+        pub fn apply_to(&self, target: &mut impl AnimationTarget) {
+            self.clip.apply_to(self.time(), target);
+        }
+    }
+
+    /// Mixes two players, from all of `from` at a weight of 0 to all of `to` at 1
+    #[derive(Clone, Debug)]
+    pub struct Blend {
+        pub from: AnimationPlayer,
+        pub to: AnimationPlayer,
+        pub weight: f32,
+    }
+
+    impl Blend {
+        pub fn new(from: AnimationPlayer, to: AnimationPlayer, weight: f32) -> Self {
+            Self { from, to, weight }
+        }
+
+        pub fn advance(&mut self, dt: f32) {
+            self.from.advance(dt);
+            self.to.advance(dt);
+        }
+
+        pub fn apply(&self, target: &str, transformation: &mut Transformation) {
+            let mut from = *transformation;
+            let mut to = *transformation;
+            self.from.apply(target, &mut from);
+            self.to.apply(target, &mut to);
+            *transformation = from.lerp(&to, self.weight);
+        }
+
+        pub fn apply_weights(&self, target: &str, weights: &mut [f32]) {
+            let mut from = weights.to_vec();
+            let mut to = weights.to_vec();
+            self.from.apply_weights(target, &mut from);
+            self.to.apply_weights(target, &mut to);
+            for (weight, (from, to)) in weights.iter_mut().zip(from.into_iter().zip(to)) {
+                *weight = from + (to - from) * self.weight;
+            }
+        }
+
+        /// Applies the blend to everything driven by either clip
+        pub fn apply_to(&self, target: &mut impl AnimationTarget) {
+            let mut targets: Vec<_> = self
+                .from
+                .clip()
+                .channels()
+                .chain(self.to.clip().channels())
+                .map(|(name, _)| name)
+                .collect();
+            targets.sort();
+            targets.dedup();
+            for name in targets {
+                if let Some(transformation) = target.target_mut(name) {
+                    self.apply(name, transformation);
+                }
+                if let Some(weights) = target.morph_weights_mut(name) {
+                    self.apply_weights(name, weights);
+                }
+            }
+        }
+    }
+}
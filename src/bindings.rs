@@ -0,0 +1,348 @@
+pub mod bindings {
+    use std::{collections::HashMap, fmt::Write, fs, path::Path};
+
+    use ruscii::keyboard::Key;
+
+    use crate::file::file::{lines, FileError};
+
+    /// Something the user can do, which keys are bound to
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Action {
+        Quit,
+        NextController,
+        MoveForward,
+        MoveBack,
+        MoveLeft,
+        MoveRight,
+        MoveUp,
+        MoveDown,
+        TurnLeft,
+        TurnRight,
+        TurnUp,
+        TurnDown,
+        ZoomIn,
+        ZoomOut,
+        SpeedUp,
+        SlowDown,
+        Pause,
+        FrontView,
+        SideView,
+        TopView,
+        PathTrace,
+        Help,
+        MenuUp,
+        MenuDown,
+        Select,
+    }
+
+    impl Action {
+        pub const ALL: [Action; 25] = [
+            Action::Quit,
+            Action::NextController,
+            Action::MoveForward,
+            Action::MoveBack,
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::TurnLeft,
+            Action::TurnRight,
+            Action::TurnUp,
+            Action::TurnDown,
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::SpeedUp,
+            Action::SlowDown,
+            Action::Pause,
+            Action::FrontView,
+            Action::SideView,
+            Action::TopView,
+            Action::PathTrace,
+            Action::Help,
+            Action::MenuUp,
+            Action::MenuDown,
+            Action::Select,
+        ];
+
+        /// The name used in bindings files
+        pub fn name(self) -> &'static str {
+            match self {
+                Action::Quit => "quit",
+                Action::NextController => "next_controller",
+                Action::MoveForward => "move_forward",
+                Action::MoveBack => "move_back",
+                Action::MoveLeft => "move_left",
+                Action::MoveRight => "move_right",
+                Action::MoveUp => "move_up",
+                Action::MoveDown => "move_down",
+                Action::TurnLeft => "turn_left",
+                Action::TurnRight => "turn_right",
+                Action::TurnUp => "turn_up",
+                Action::TurnDown => "turn_down",
+                Action::ZoomIn => "zoom_in",
+                Action::ZoomOut => "zoom_out",
+                Action::SpeedUp => "speed_up",
+                Action::SlowDown => "slow_down",
+                Action::Pause => "pause",
+                Action::FrontView => "front_view",
+                Action::SideView => "side_view",
+                Action::TopView => "top_view",
+                Action::PathTrace => "path_trace",
+                Action::Help => "help",
+                Action::MenuUp => "menu_up",
+                Action::MenuDown => "menu_down",
+                Action::Select => "select",
+            }
+        }
+
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::ALL.into_iter().find(|x| x.name() == name)
+        }
+
+        /// The keys bound to the action unless a bindings file says otherwise
+        pub fn default_keys(self) -> Vec<Key> {
+            match self {
+                Action::Quit => vec![Key::Esc],
+                Action::NextController => vec![Key::Tab],
+                Action::MoveForward => vec![Key::W],
+                Action::MoveBack => vec![Key::S],
+                Action::MoveLeft => vec![Key::A],
+                Action::MoveRight => vec![Key::D],
+                Action::MoveUp => vec![Key::E],
+                Action::MoveDown => vec![Key::Q],
+                Action::TurnLeft => vec![Key::Left],
+                Action::TurnRight => vec![Key::Right],
+                Action::TurnUp => vec![Key::Up],
+                Action::TurnDown => vec![Key::Down],
+                Action::ZoomIn => vec![Key::Equal],
+                Action::ZoomOut => vec![Key::Minus],
+                Action::SpeedUp => vec![Key::Equal, Key::RightBracket],
+                Action::SlowDown => vec![Key::Minus, Key::LeftBracket],
+                Action::Pause => vec![Key::Space],
+                // like a numpad in most modelling software
+                Action::FrontView => vec![Key::Num1],
+                Action::SideView => vec![Key::Num3],
+                Action::TopView => vec![Key::Num7],
+                Action::PathTrace => vec![Key::P],
+                Action::Help => vec![Key::F1, Key::H],
+                Action::MenuUp => vec![Key::Up, Key::W],
+                Action::MenuDown => vec![Key::Down, Key::S],
+                Action::Select => vec![Key::Enter, Key::Space],
+            }
+        }
+    }
+
+    /// Which keys are bound to each action. One key can be bound to several actions,
+    /// and it's up to whoever's listening to ignore the ones that don't apply.
+    ///
+    /// Bindings files have a line for each action to rebind, with its name, `=`,
+    /// then any number of key names separated by spaces. Actions which aren't
+    /// mentioned keep their default keys, and anything after a `#` is a comment:
+    /// ```text
+    /// # arrows for moving, and nothing for pausing
+    /// move_forward = up w
+    /// move_back = down s # or backwards
+    /// pause =
+    /// ```
+    /// Keys are named as in `ruscii::keyboard::Key`, ignoring case, so `esc`, `pageup`
+    /// and `num1`. Letters, digits and punctuation can also be written as themselves.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Bindings {
+        keys: HashMap<Action, Vec<Key>>,
+    }
+
+    impl Default for Bindings {
+        fn default() -> Self {
+            Self {
+                keys: Action::ALL
+                    .into_iter()
+                    .map(|x| (x, x.default_keys()))
+                    .collect(),
+            }
+        }
+    }
+
+    impl Bindings {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// The default bindings, overridden by a bindings file
+        pub fn load(path: impl AsRef<Path>) -> Result<Self, FileError> {
+            Self::parse(&fs::read_to_string(path)?)
+        }
+
+        /// The default bindings, overridden by the contents of a bindings file
+        pub fn parse(source: &str) -> Result<Self, FileError> {
+            let mut bindings = Self::new();
+            bindings.override_with(source)?;
+            Ok(bindings)
+        }
+
+        /// Rebinds the actions mentioned in the contents of a bindings file.
+        /// Nothing changes if there's an error.
+        pub fn override_with(&mut self, source: &str) -> Result<(), FileError> {
+            let mut overrides = Vec::new();
+            for (line_number, line) in lines(source, &[]) {
+                let error = |message: String| FileError::Parse {
+                    line: line_number,
+                    message,
+                };
+                let Some((name, keys)) = line.split_once('=') else {
+                    return Err(error("expected `action = keys`".to_string()));
+                };
+                let name = name.trim();
+                let action = Action::from_name(name)
+                    .ok_or_else(|| error(format!("unknown action `{name}`")))?;
+                let keys = keys
+                    .split_whitespace()
+                    .map(|x| key_from_name(x).ok_or_else(|| error(format!("unknown key `{x}`"))))
+                    .collect::<Result<Vec<_>, _>>()?;
+                overrides.push((action, keys));
+            }
+            self.keys.extend(overrides);
+            Ok(())
+        }
+
+        /// The bindings as a bindings file, with every action listed
+        pub fn to_config(&self) -> String {
+            let mut output = String::new();
+            for action in Action::ALL {
+                let keys: Vec<String> = self.keys(action).iter().map(|&x| key_name(x)).collect();
+                writeln!(output, "{} = {}", action.name(), keys.join(" ")).unwrap();
+            }
+            output
+        }
+
+        pub fn keys(&self, action: Action) -> &[Key] {
+            self.keys.get(&action).map_or(&[], Vec::as_slice)
+        }
+
+        /// Replaces the keys bound to an action
+        pub fn bind(&mut self, action: Action, keys: &[Key]) {
+            self.keys.insert(action, keys.to_vec());
+        }
+
+        /// Whether `key` is bound to `action`
+        pub fn matches(&self, action: Action, key: Key) -> bool {
+            self.keys(action).contains(&key)
+        }
+
+        /// Every action `key` is bound to, in the order of `Action::ALL`
+        pub fn actions(&self, key: Key) -> Vec<Action> {
+            Action::ALL
+                .into_iter()
+                .filter(|&x| self.matches(x, key))
+                .collect()
+        }
+    }
+
+    /// Every key which can be bound
+    const KEYS: [Key; 75] = [
+        Key::Esc,
+        Key::Space,
+        Key::Enter,
+        Key::Backspace,
+        Key::CapsLock,
+        Key::Tab,
+        Key::Up,
+        Key::Down,
+        Key::Left,
+        Key::Right,
+        Key::Home,
+        Key::End,
+        Key::PageUp,
+        Key::PageDown,
+        Key::Insert,
+        Key::Delete,
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::V,
+        Key::W,
+        Key::X,
+        Key::Y,
+        Key::Z,
+        Key::Num0,
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+        Key::F9,
+        Key::F10,
+        Key::F11,
+        Key::F12,
+        Key::Grave,
+        Key::Minus,
+        Key::Equal,
+        Key::LeftBracket,
+        Key::RightBracket,
+        Key::BackSlash,
+        Key::Semicolon,
+        Key::Apostrophe,
+        Key::Comma,
+        Key::Dot,
+        Key::Slash,
+    ];
+
+    /// The key's name in bindings files
+    pub fn key_name(key: Key) -> String {
+        format!("{key:?}").to_lowercase()
+    }
+
+    /// Finds a key by its name, ignoring case, or by the character it types
+    pub fn key_from_name(name: &str) -> Option<Key> {
+        let by_character = match name {
+            "`" => Some(Key::Grave),
+            "-" => Some(Key::Minus),
+            "=" => Some(Key::Equal),
+            "[" => Some(Key::LeftBracket),
+            "]" => Some(Key::RightBracket),
+            "\\" => Some(Key::BackSlash),
+            ";" => Some(Key::Semicolon),
+            "'" => Some(Key::Apostrophe),
+            "," => Some(Key::Comma),
+            "." => Some(Key::Dot),
+            "/" => Some(Key::Slash),
+            digit if digit.len() == 1 && digit.chars().all(|x| x.is_ascii_digit()) => {
+                key_from_name(&format!("num{digit}"))
+            }
+            _ => None,
+        };
+        by_character.or_else(|| {
+            KEYS.into_iter()
+                .find(|&x| key_name(x).eq_ignore_ascii_case(name))
+        })
+    }
+}
@@ -0,0 +1,164 @@
+pub mod camera {
+    use crate::{
+        collision::collision::Ray,
+        matrix::matrix::{Matrix, Vec3},
+        shapes::shapes::BoundingSphere,
+    };
+
+    /// A perspective camera looking from `position` towards `target`
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Camera {
+        position: Vec3,
+        target: Vec3,
+        up: Vec3,
+        /// Vertical field of view, in radians
+        fov: f32,
+        near: f32,
+        /// How many times taller a terminal cell is than it is wide
+        cell_aspect: f32,
+    }
+
+    impl Camera {
+        pub fn new(position: Vec3, target: Vec3) -> Self {
+            Self {
+                position,
+                target,
+                up: matrix![0; 1; 0],
+                fov: 60f32.to_radians(),
+                near: 0.1,
+                cell_aspect: 2.0,
+            }
+        }
+
+        pub fn position(&self) -> Vec3 {
+            self.position
+        }
+
+        pub fn set_position(&mut self, position: Vec3) {
+            self.position = position;
+        }
+
+        pub fn target(&self) -> Vec3 {
+            self.target
+        }
+
+        pub fn set_target(&mut self, target: Vec3) {
+            self.target = target;
+        }
+
+        pub fn fov(&self) -> f32 {
+            self.fov
+        }
+
+        pub fn set_fov(&mut self, fov: f32) {
+            self.fov = fov;
+        }
+
+        pub fn near(&self) -> f32 {
+            self.near
+        }
+
+        pub fn set_near(&mut self, near: f32) {
+            self.near = near;
+        }
+
+        pub fn cell_aspect(&self) -> f32 {
+            self.cell_aspect
+        }
+
+        pub fn set_cell_aspect(&mut self, cell_aspect: f32) {
+            self.cell_aspect = cell_aspect;
+        }
+
+        /// Unit vector pointing from the camera towards its target,
+        /// or down the negative z axis if the camera is at its target
+        pub fn forward(&self) -> Vec3 {
+            let offset = self.target - self.position;
+            if offset.length() > 0.0 {
+                offset.normalized()
+            } else {
+                matrix![0; 0; -1]
+            }
+        }
+
+        /// Unit vector pointing right on the screen,
+        /// or along the x axis if the camera looks straight up or down
+        pub fn right(&self) -> Vec3 {
+            let right = self.forward().cross(&self.up);
+            if right.length() > 0.0 {
+                right.normalized()
+            } else {
+                matrix![1; 0; 0]
+            }
+        }
+
+        /// Unit vector pointing upwards on the screen
+        pub fn up(&self) -> Vec3 {
+            self.right().cross(&self.forward())
+        }
+
+        /// Converts world coordinates to view coordinates,
+        /// where the camera looks down the negative z axis
+        pub fn view_matrix(&self) -> Matrix<4, 4> {
+            let [right, up, forward] = [self.right(), self.up(), self.forward()];
+            matrix! {
+                right.x(), right.y(), right.z(), -right.dot(&self.position);
+                up.x(), up.y(), up.z(), -up.dot(&self.position);
+                -forward.x(), -forward.y(), -forward.z(), forward.dot(&self.position);
+                0, 0, 0, 1
+            }
+        }
+
+        /// Width divided by height of the visible area, in world units
+        pub fn aspect(&self, win_x: usize, win_y: usize) -> f32 {
+            win_x as f32 / (win_y as f32 * self.cell_aspect)
+        }
+
+        /// Projects a point onto the window, returning its column, row and distance in
+        /// front of the camera, or `None` if it's closer than the near plane
+        pub fn project(&self, point: Vec3, win_x: usize, win_y: usize) -> Option<Vec3> {
+            let view = (self.view_matrix() * point.extend(1.0)).truncate();
+            if -view.z() < self.near {
+                return None;
+            }
+            Some(self.project_view(view, win_x, win_y))
+        }
+
+        /// Like `project`, but for a point already in view coordinates,
+        /// and without checking the near plane
+        pub fn project_view(&self, view: Vec3, win_x: usize, win_y: usize) -> Vec3 {
+            let depth = -view.z();
+            let half_height = (self.fov * 0.5).tan() * depth;
+            let half_width = half_height * self.aspect(win_x, win_y);
+            matrix![
+                (view.x() / half_width + 1.0) * 0.5 * win_x as f32;
+                (1.0 - view.y() / half_height) * 0.5 * win_y as f32;
+                depth
+            ]
+        }
+
+        /// The ray from the camera through a point on the window, given as a column and row
+        /// which can be fractional. This is the opposite of `project`.
+        pub fn ray(&self, column: f32, row: f32, win_x: usize, win_y: usize) -> Ray {
+            let half_height = (self.fov * 0.5).tan();
+            let half_width = half_height * self.aspect(win_x, win_y);
+            let x = (column / win_x as f32 * 2.0 - 1.0) * half_width;
+            let y = (1.0 - row / win_y as f32 * 2.0) * half_height;
+            Ray::new(
+                self.position,
+                self.forward() + self.right() * x + self.up() * y,
+            )
+        }
+
+        /// Moves the camera backwards or forwards along its current view direction until
+        /// `sphere` exactly fits inside the window, and points it at the sphere's centre
+        pub fn frame(&mut self, sphere: &BoundingSphere, win_x: usize, win_y: usize) {
+            let half_fov_y = self.fov * 0.5;
+            let half_fov_x = (half_fov_y.tan() * self.aspect(win_x, win_y)).atan();
+            let distance = sphere.radius / half_fov_x.min(half_fov_y).sin();
+            let forward = self.forward();
+            self.target = sphere.centre;
+            self.position = sphere.centre - forward * distance.max(self.near + sphere.radius);
+        }
+    }
+}
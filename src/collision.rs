@@ -1,5 +1,5 @@
 pub mod collision {
-    use crate::matrix::matrix::Vec2;
+    use crate::matrix::matrix::{Vec2, Vec3};
 
     pub trait CollisionTrait: std::fmt::Debug + Clone {
         fn collision(&self, other: &Collider, threshold: f32) -> CollisionResult;
@@ -21,6 +21,7 @@ pub mod collision {
         Infinite,
     }
     impl CollisionResult {
+        #[allow(dead_code, clippy::match_ref_pats)]
         fn as_vec(&self) -> Option<Vec<Vec2>> {
             match &self {
                 &CollisionResult::None => None,
@@ -37,6 +38,7 @@ pub mod collision {
     pub struct LineCollider {
         position: Vec2,
         direction: Vec2,
+        #[allow(dead_code)]
         bounded: bool,
     }
     impl LineCollider {
@@ -113,13 +115,16 @@ pub mod collision {
                         CollisionResult::One(line.position + line.direction * b)
                     }
                 }
+                #[allow(unused_variables)]
                 Collider::Circle(circle) => todo!(),
+                #[allow(unused_variables)]
                 Collider::Triangle(triangle) => todo!(),
             }
         }
     }
 
     #[derive(Debug, Clone)]
+    #[allow(dead_code)]
     pub struct CircleCollider {
         centre: Vec2,
         radius: f32,
@@ -129,6 +134,7 @@ pub mod collision {
             Self { centre, radius }
         }
     }
+    #[allow(unused_variables)]
     impl CollisionTrait for CircleCollider {
         fn collision(&self, other: &Collider, threshold: f32) -> CollisionResult {
             todo!()
@@ -140,6 +146,7 @@ pub mod collision {
     }
 
     #[derive(Debug, Clone)]
+    #[allow(dead_code)]
     pub struct TriangleCollider {
         points: [Vec2; 3],
     }
@@ -148,6 +155,7 @@ pub mod collision {
             Self { points: *points }
         }
     }
+    #[allow(unused_variables)]
     impl CollisionTrait for TriangleCollider {
         fn collision(&self, other: &Collider, threshold: f32) -> CollisionResult {
             todo!()
@@ -157,4 +165,71 @@ pub mod collision {
             todo!()
         }
     }
+
+    /// A half-line in 3D defined as `r = origin + lambda direction`, where `lambda >= 0`
+    #[derive(Debug, Clone, Copy)]
+    pub struct Ray {
+        pub origin: Vec3,
+        /// Always has a length of 1, so `lambda` is the distance along the ray
+        pub direction: Vec3,
+    }
+
+    /// Where a ray meets a triangle
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct RayHit {
+        /// How far along the ray the hit is
+        pub distance: f32,
+        /// Barycentric weights of the triangle's corners at the hit
+        pub weights: [f32; 3],
+    }
+
+    impl Ray {
+        pub fn new(origin: Vec3, direction: Vec3) -> Self {
+            Self {
+                origin,
+                direction: direction.normalized(),
+            }
+        }
+
+        pub fn at(&self, distance: f32) -> Vec3 {
+            self.origin + self.direction * distance
+        }
+
+        /// The closest hit with a triangle from either side, using the Moller-Trumbore
+        /// algorithm. Hits closer than `min_distance` are ignored, which stops rays
+        /// leaving a surface from hitting it again.
+        pub fn intersect_triangle(&self, points: &[Vec3; 3], min_distance: f32) -> Option<RayHit> {
+            let [a, b, c] = points;
+            let (edge1, edge2) = (*b - *a, *c - *a);
+            let p = self.direction.cross(&edge2);
+            let determinant = edge1.dot(&p);
+            if determinant.abs() < 1e-9 {
+                return None;
+            }
+            let offset = self.origin - *a;
+            let u = offset.dot(&p) / determinant;
+            if !(0.0..=1.0).contains(&u) {
+                return None;
+            }
+            let q = offset.cross(&edge1);
+            let v = self.direction.dot(&q) / determinant;
+            if v < 0.0 || u + v > 1.0 {
+                return None;
+            }
+            let distance = edge2.dot(&q) / determinant;
+            (distance >= min_distance).then_some(RayHit {
+                distance,
+                weights: [1.0 - u - v, u, v],
+            })
+        }
+
+        /// Whether the ray passes within `radius` of `centre` anywhere in front of its origin
+        pub fn hits_sphere(&self, centre: Vec3, radius: f32) -> bool {
+            let offset = centre - self.origin;
+            let along = offset.dot(&self.direction);
+            let sqr_distance = offset.sqr_length() - along * along;
+            sqr_distance <= radius * radius
+                && (along >= 0.0 || offset.sqr_length() <= radius * radius)
+        }
+    }
 }
@@ -0,0 +1,197 @@
+pub mod colour {
+    use std::ops::{Add, Mul, Sub};
+
+    use ruscii::terminal::Color;
+
+    /// An RGB colour with components from 0 to 1
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Colour {
+        pub r: f32,
+        pub g: f32,
+        pub b: f32,
+    }
+
+    impl Colour {
+        pub const BLACK: Colour = Colour::new(0.0, 0.0, 0.0);
+        pub const WHITE: Colour = Colour::new(1.0, 1.0, 1.0);
+
+        pub const fn new(r: f32, g: f32, b: f32) -> Self {
+            Self { r, g, b }
+        }
+
+        pub const fn grey(value: f32) -> Self {
+            Self::new(value, value, value)
+        }
+
+        pub fn from_rgb8(r: u8, g: u8, b: u8) -> Self {
+            Self::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+        }
+
+        pub fn to_rgb8(self) -> [u8; 3] {
+            [self.r, self.g, self.b].map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8)
+        }
+
+        /// Perceived brightness, from 0 to 1 for colours within range
+        pub fn luminance(&self) -> f32 {
+            0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+        }
+
+        pub fn max_component(&self) -> f32 {
+            self.r.max(self.g).max(self.b)
+        }
+
+        pub fn clamped(self) -> Self {
+            Self::new(
+                self.r.clamp(0.0, 1.0),
+                self.g.clamp(0.0, 1.0),
+                self.b.clamp(0.0, 1.0),
+            )
+        }
+
+        pub fn lerp(self, other: Colour, t: f32) -> Self {
+            self + (other - self) * t
+        }
+
+        fn sqr_distance(&self, other: &Colour) -> f32 {
+            let difference = *self - *other;
+            difference.r * difference.r + difference.g * difference.g + difference.b * difference.b
+        }
+    }
+
+    impl Default for Colour {
+        fn default() -> Self {
+            Colour::WHITE
+        }
+    }
+
+    impl Add<Colour> for Colour {
+        type Output = Colour;
+
+        fn add(self, rhs: Colour) -> Self::Output {
+            Colour::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b)
+        }
+    }
+
+    impl Sub<Colour> for Colour {
+        type Output = Colour;
+
+        fn sub(self, rhs: Colour) -> Self::Output {
+            Colour::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b)
+        }
+    }
+
+    /// Modulates one colour by another, component by component
+    impl Mul<Colour> for Colour {
+        type Output = Colour;
+
+        fn mul(self, rhs: Colour) -> Self::Output {
+            Colour::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b)
+        }
+    }
+
+    impl Mul<f32> for Colour {
+        type Output = Colour;
+
+        fn mul(self, rhs: f32) -> Self::Output {
+            Colour::new(self.r * rhs, self.g * rhs, self.b * rhs)
+        }
+    }
+
+    /// How many colours the terminal can show
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ColourMode {
+        /// 24 bit colour, which `Framebuffer::to_ansi` writes out exactly. ruscii can only draw
+        /// xterm palette colours, so when drawing through a `Pencil` this falls back to the
+        /// 256 colour palette.
+        TrueColour,
+        Palette256,
+        Palette16,
+        Monochrome,
+    }
+
+    impl ColourMode {
+        /// Guesses from the `COLORTERM` and `TERM` environment variables
+        pub fn detect() -> Self {
+            let colour_term = std::env::var("COLORTERM").unwrap_or_default();
+            let term = std::env::var("TERM").unwrap_or_default();
+            if colour_term == "truecolor" || colour_term == "24bit" {
+                ColourMode::TrueColour
+            } else if term.contains("256color") {
+                ColourMode::Palette256
+            } else if term == "dumb" {
+                ColourMode::Monochrome
+            } else {
+                ColourMode::Palette16
+            }
+        }
+
+        /// The closest colour ruscii can draw in this mode
+        pub fn quantise(&self, colour: Colour) -> Color {
+            match self {
+                ColourMode::TrueColour | ColourMode::Palette256 => {
+                    Color::Xterm(nearest_256(colour.clamped()))
+                }
+                ColourMode::Palette16 => Color::Xterm(nearest_16(colour.clamped())),
+                ColourMode::Monochrome => Color::White,
+            }
+        }
+    }
+
+    /// The standard xterm values for the 16 system colours
+    pub const PALETTE_16: [[u8; 3]; 16] = [
+        [0, 0, 0],
+        [205, 0, 0],
+        [0, 205, 0],
+        [205, 205, 0],
+        [0, 0, 238],
+        [205, 0, 205],
+        [0, 205, 205],
+        [229, 229, 229],
+        [127, 127, 127],
+        [255, 0, 0],
+        [0, 255, 0],
+        [255, 255, 0],
+        [92, 92, 255],
+        [255, 0, 255],
+        [0, 255, 255],
+        [255, 255, 255],
+    ];
+
+    /// The levels of each component in the xterm 6x6x6 colour cube
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    fn nearest_16(colour: Colour) -> u8 {
+        (0..16)
+            .min_by(|&a, &b| {
+                let [a, b] = [a, b].map(|x: usize| {
+                    let [r, g, b] = PALETTE_16[x];
+                    colour.sqr_distance(&Colour::from_rgb8(r, g, b))
+                });
+                a.total_cmp(&b)
+            })
+            .unwrap() as u8
+    }
+
+    /// Picks between the closest colour in the 6x6x6 cube (16 to 231)
+    /// and the closest of the 24 greys (232 to 255)
+    fn nearest_256(colour: Colour) -> u8 {
+        let level = |x: f32| {
+            (0..6)
+                .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - (x * 255.0).round() as i32).abs())
+                .unwrap()
+        };
+        let [r, g, b] = [level(colour.r), level(colour.g), level(colour.b)];
+        let cube = Colour::from_rgb8(CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+        let grey_index = ((colour.luminance() * 255.0 - 8.0) / 10.0)
+            .round()
+            .clamp(0.0, 23.0) as u8;
+        let grey = Colour::grey((8 + 10 * grey_index as u32) as f32 / 255.0);
+
+        if colour.sqr_distance(&grey) < colour.sqr_distance(&cube) {
+            232 + grey_index
+        } else {
+            16 + 36 * r as u8 + 6 * g as u8 + b as u8
+        }
+    }
+}
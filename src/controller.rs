@@ -0,0 +1,632 @@
+pub mod controller {
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    use crate::{
+        bindings::bindings::Action,
+        camera::camera::Camera,
+        matrix::matrix::Vec3,
+        screen::screen::{Modifiers, MouseButton},
+    };
+
+    /// Terminal cells are about twice as tall as they are wide,
+    /// so dragging a row moves the mouse as far as two columns
+    const CELL_ASPECT: f32 = 2.0;
+
+    /// How close the camera can get to looking straight up or down,
+    /// where its up direction stops making sense
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+    /// Something which moves a camera in response to input.
+    /// Scenes forward the actions bound to keys and the mouse input to a controller,
+    /// call `update` once a frame, then `apply` it to their camera.
+    pub trait CameraController {
+        /// Moves the controller to wherever the camera is, without smoothing,
+        /// keeping its settings
+        fn reset(&mut self, camera: &Camera);
+
+        /// Jumps, or glides if the controller is smoothed, to one of the standard views
+        fn snap(&mut self, view: View);
+
+        /// Applies any held keys and moves towards where the controller is heading
+        fn update(&mut self, dt: f32);
+
+        /// Moves the camera to wherever the controller currently is
+        fn apply(&self, camera: &mut Camera);
+
+        fn action_pressed(&mut self, action: Action) {
+            if let Some(view) = View::from_action(action) {
+                self.snap(view);
+            }
+        }
+
+        /// Called every frame for every action whose key is being held, before `update`
+        fn action_down(&mut self, _action: Action) {}
+
+        fn mouse_down(&mut self, _button: MouseButton, _position: (usize, usize)) {}
+
+        fn mouse_up(&mut self, _button: MouseButton, _position: (usize, usize)) {}
+
+        fn mouse_dragged(
+            &mut self,
+            _button: MouseButton,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+        ) {
+        }
+
+        fn mouse_scrolled(&mut self, _amount: i32) {}
+    }
+
+    /// The standard views a controller can snap to, looking at the scene from
+    /// the positive z axis, the positive x axis, or above
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum View {
+        Front,
+        Side,
+        Top,
+    }
+
+    impl View {
+        pub fn from_action(action: Action) -> Option<Self> {
+            match action {
+                Action::FrontView => Some(Self::Front),
+                Action::SideView => Some(Self::Side),
+                Action::TopView => Some(Self::Top),
+                _ => None,
+            }
+        }
+
+        /// The yaw and pitch of the direction from the scene towards the camera
+        pub fn angles(self) -> (f32, f32) {
+            match self {
+                Self::Front => (0.0, 0.0),
+                Self::Side => (FRAC_PI_2, 0.0),
+                Self::Top => (0.0, MAX_PITCH),
+            }
+        }
+    }
+
+    /// How much of the remaining distance to close this frame, where `smoothing` is
+    /// roughly how many seconds it takes to close most of it
+    fn catch_up(dt: f32, smoothing: f32) -> f32 {
+        if smoothing <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-dt / smoothing).exp()
+        }
+    }
+
+    /// The angle equivalent to `angle` closest to `from`,
+    /// so turning to it never goes the long way round
+    fn nearest_angle(from: f32, angle: f32) -> f32 {
+        from + (angle - from + PI).rem_euclid(TAU) - PI
+    }
+
+    /// The point `distance` away from `target` in the direction given by `yaw` and `pitch`
+    fn orbit_position(target: Vec3, distance: f32, yaw: f32, pitch: f32) -> Vec3 {
+        let horizontal = pitch.cos();
+        let direction = matrix![
+            horizontal * yaw.sin();
+            pitch.sin();
+            horizontal * yaw.cos()
+        ];
+        target + direction * distance
+    }
+
+    /// The yaw and pitch of `offset`, as used by `orbit_position`
+    fn orbit_angles(offset: Vec3) -> (f32, f32) {
+        let distance = offset.length();
+        if distance > 0.0 {
+            let pitch = (offset.y() / distance).clamp(-1.0, 1.0).asin();
+            (offset.x().atan2(offset.z()), pitch)
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    /// Where an orbiting camera is, as opposed to where it's heading
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Orbit {
+        target: Vec3,
+        distance: f32,
+        yaw: f32,
+        pitch: f32,
+    }
+
+    /// Moves a camera around a target. Dragging rotates around it, the scroll wheel zooms
+    /// in and out, and dragging with the middle button or a modifier held pans.
+    /// The turning actions also rotate, and the zooming actions zoom.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct OrbitController {
+        pub target: Vec3,
+        pub distance: f32,
+        /// Radians around the y axis, where 0 looks down the negative z axis
+        pub yaw: f32,
+        /// Radians above the target
+        pub pitch: f32,
+        /// Radians turned for each column dragged
+        pub rotate_speed: f32,
+        /// Radians turned per second while a turning key is held
+        pub turn_speed: f32,
+        /// How much of the distance each step of the scroll wheel moves in or out
+        pub zoom_speed: f32,
+        /// How many steps of the scroll wheel a second of holding a zoom key is worth
+        pub zoom_rate: f32,
+        /// How much of the distance the target moves for each column dragged when panning
+        pub pan_speed: f32,
+        pub min_distance: f32,
+        pub max_distance: f32,
+        /// Roughly how many seconds the camera takes to catch up with the controls,
+        /// or 0 to follow them exactly
+        pub smoothing: f32,
+        /// Where the mouse was when it was last pressed or dragged
+        last_position: Option<(usize, usize)>,
+        /// Yaw, pitch and zoom directions from the keys held this frame
+        held: Vec3,
+        /// Where the camera is while it catches up, if it's smoothed
+        current: Option<Orbit>,
+    }
+
+    impl OrbitController {
+        pub fn new(target: Vec3, distance: f32) -> Self {
+            Self {
+                target,
+                distance,
+                yaw: 0.0,
+                pitch: 0.0,
+                rotate_speed: 0.05,
+                turn_speed: 1.5,
+                zoom_speed: 0.1,
+                zoom_rate: 10.0,
+                pan_speed: 0.02,
+                min_distance: 0.1,
+                max_distance: 1000.0,
+                smoothing: 0.0,
+                last_position: None,
+                held: matrix![0; 0; 0],
+                current: None,
+            }
+        }
+
+        /// A controller which leaves the camera where it is
+        pub fn from_camera(camera: &Camera) -> Self {
+            let mut controller = Self::new(camera.target(), 0.0);
+            controller.reset(camera);
+            controller
+        }
+
+        pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+            self.smoothing = smoothing;
+            self
+        }
+
+        /// Where the camera is heading
+        pub fn position(&self) -> Vec3 {
+            orbit_position(self.target, self.distance, self.yaw, self.pitch)
+        }
+
+        fn goal(&self) -> Orbit {
+            Orbit {
+                target: self.target,
+                distance: self.distance,
+                yaw: self.yaw,
+                pitch: self.pitch,
+            }
+        }
+
+        /// Moves in or out by `amount` steps of the scroll wheel
+        fn zoom(&mut self, amount: f32) {
+            self.distance = (self.distance * (1.0 - self.zoom_speed).powf(amount))
+                .clamp(self.min_distance, self.max_distance);
+        }
+
+        /// Moves the target across the view, so the scene moves right by `dx` columns
+        /// and down by `dy` columns' worth
+        fn pan(&mut self, dx: f32, dy: f32) {
+            let camera = Camera::new(self.position(), self.target);
+            let scale = self.distance * self.pan_speed;
+            self.target = self.target - camera.right() * (dx * scale) + camera.up() * (dy * scale);
+        }
+    }
+
+    impl CameraController for OrbitController {
+        fn reset(&mut self, camera: &Camera) {
+            let offset = camera.position() - camera.target();
+            (self.yaw, self.pitch) = orbit_angles(offset);
+            self.target = camera.target();
+            self.distance = offset.length();
+            self.current = (self.smoothing > 0.0).then_some(self.goal());
+        }
+
+        fn snap(&mut self, view: View) {
+            let (yaw, pitch) = view.angles();
+            self.yaw = nearest_angle(self.current.map_or(self.yaw, |x| x.yaw), yaw);
+            self.pitch = pitch;
+        }
+
+        fn update(&mut self, dt: f32) {
+            self.yaw += self.held.x() * self.turn_speed * dt;
+            self.pitch =
+                (self.pitch + self.held.y() * self.turn_speed * dt).clamp(-MAX_PITCH, MAX_PITCH);
+            self.zoom(self.held.z() * self.zoom_rate * dt);
+            self.held = matrix![0; 0; 0];
+
+            let goal = self.goal();
+            self.current = match self.current {
+                Some(current) if self.smoothing > 0.0 => {
+                    let t = catch_up(dt, self.smoothing);
+                    Some(Orbit {
+                        target: current.target + (goal.target - current.target) * t,
+                        distance: current.distance + (goal.distance - current.distance) * t,
+                        yaw: current.yaw + (goal.yaw - current.yaw) * t,
+                        pitch: current.pitch + (goal.pitch - current.pitch) * t,
+                    })
+                }
+                None if self.smoothing > 0.0 => Some(goal),
+                _ => None,
+            };
+        }
+
+        fn apply(&self, camera: &mut Camera) {
+            let orbit = self.current.unwrap_or(self.goal());
+            camera.set_position(orbit_position(
+                orbit.target,
+                orbit.distance,
+                orbit.yaw,
+                orbit.pitch,
+            ));
+            camera.set_target(orbit.target);
+        }
+
+        fn action_down(&mut self, action: Action) {
+            let direction = match action {
+                Action::TurnLeft => matrix![-1; 0; 0],
+                Action::TurnRight => matrix![1; 0; 0],
+                Action::TurnUp => matrix![0; 1; 0],
+                Action::TurnDown => matrix![0; -1; 0],
+                Action::ZoomIn => matrix![0; 0; 1],
+                Action::ZoomOut => matrix![0; 0; -1],
+                _ => return,
+            };
+            self.held = self.held + direction;
+        }
+
+        fn mouse_down(&mut self, _button: MouseButton, position: (usize, usize)) {
+            self.last_position = Some(position);
+        }
+
+        fn mouse_up(&mut self, _button: MouseButton, _position: (usize, usize)) {
+            self.last_position = None;
+        }
+
+        fn mouse_dragged(
+            &mut self,
+            button: MouseButton,
+            position: (usize, usize),
+            modifiers: Modifiers,
+        ) {
+            let last = self.last_position.replace(position).unwrap_or(position);
+            let dx = position.0 as f32 - last.0 as f32;
+            let dy = (position.1 as f32 - last.1 as f32) * CELL_ASPECT;
+            if button == MouseButton::Middle || modifiers.any() {
+                self.pan(dx, dy);
+            } else {
+                // the scene follows the mouse, so the camera goes the other way
+                self.yaw -= dx * self.rotate_speed;
+                self.pitch = (self.pitch + dy * self.rotate_speed).clamp(-MAX_PITCH, MAX_PITCH);
+            }
+        }
+
+        fn mouse_scrolled(&mut self, amount: i32) {
+            self.zoom(amount as f32);
+        }
+    }
+
+    /// Flies a camera around freely with the moving actions, which are W, A, S, D, E and Q
+    /// by default, while the turning actions look around.
+    /// The scroll wheel speeds up and slows down, as well as the actions for that.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct FreeFlyController {
+        pub position: Vec3,
+        /// Radians around the y axis, where 0 looks down the negative z axis
+        pub yaw: f32,
+        /// Radians above the horizon
+        pub pitch: f32,
+        /// Units moved per second
+        pub speed: f32,
+        /// How much speeding up or slowing down multiplies or divides the speed by
+        pub speed_step: f32,
+        pub min_speed: f32,
+        pub max_speed: f32,
+        /// Radians turned per second while a turning key is held
+        pub turn_speed: f32,
+        /// Roughly how many seconds the camera takes to get up to speed, come to a stop
+        /// and finish turning, or 0 to follow the controls exactly
+        pub smoothing: f32,
+        velocity: Vec3,
+        /// Right, up and forward directions from the keys held this frame
+        held: Vec3,
+        /// Yaw and pitch directions from the turning keys held this frame
+        turning: (f32, f32),
+        /// Where the camera is looking while it catches up, if it's smoothed
+        current: Option<(f32, f32)>,
+    }
+
+    impl FreeFlyController {
+        pub fn new(position: Vec3) -> Self {
+            Self {
+                position,
+                yaw: 0.0,
+                pitch: 0.0,
+                speed: 2.0,
+                speed_step: 1.5,
+                min_speed: 0.01,
+                max_speed: 1000.0,
+                turn_speed: 1.5,
+                smoothing: 0.0,
+                velocity: matrix![0; 0; 0],
+                held: matrix![0; 0; 0],
+                turning: (0.0, 0.0),
+                current: None,
+            }
+        }
+
+        /// A controller which leaves the camera where it is
+        pub fn from_camera(camera: &Camera) -> Self {
+            let mut controller = Self::new(camera.position());
+            controller.reset(camera);
+            controller
+        }
+
+        pub fn with_speed(mut self, speed: f32) -> Self {
+            self.speed = speed;
+            self
+        }
+
+        pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+            self.smoothing = smoothing;
+            self
+        }
+
+        /// Multiplies the speed by `speed_step` this many times, or divides it if negative
+        pub fn change_speed(&mut self, steps: i32) {
+            self.speed =
+                (self.speed * self.speed_step.powi(steps)).clamp(self.min_speed, self.max_speed);
+        }
+
+        /// How fast the camera is moving, in units per second
+        pub fn velocity(&self) -> Vec3 {
+            self.velocity
+        }
+
+        /// Unit vector the camera looks along, for a yaw and pitch
+        fn forward(yaw: f32, pitch: f32) -> Vec3 {
+            -orbit_position(matrix![0; 0; 0], 1.0, yaw, -pitch)
+        }
+    }
+
+    impl CameraController for FreeFlyController {
+        fn reset(&mut self, camera: &Camera) {
+            self.position = camera.position();
+            let (yaw, pitch) = orbit_angles(-camera.forward());
+            (self.yaw, self.pitch) = (yaw, -pitch);
+            self.velocity = matrix![0; 0; 0];
+            self.current = (self.smoothing > 0.0).then_some((self.yaw, self.pitch));
+        }
+
+        fn snap(&mut self, view: View) {
+            let (yaw, pitch) = view.angles();
+            self.yaw = nearest_angle(self.current.map_or(self.yaw, |x| x.0), yaw);
+            self.pitch = -pitch;
+        }
+
+        fn update(&mut self, dt: f32) {
+            self.yaw += self.turning.0 * self.turn_speed * dt;
+            self.pitch =
+                (self.pitch + self.turning.1 * self.turn_speed * dt).clamp(-MAX_PITCH, MAX_PITCH);
+            self.turning = (0.0, 0.0);
+
+            let t = catch_up(dt, self.smoothing);
+            let (yaw, pitch) = match self.current {
+                Some((yaw, pitch)) if self.smoothing > 0.0 => {
+                    (yaw + (self.yaw - yaw) * t, pitch + (self.pitch - pitch) * t)
+                }
+                _ => (self.yaw, self.pitch),
+            };
+            self.current = (self.smoothing > 0.0).then_some((yaw, pitch));
+
+            // moving is relative to where the camera is looking,
+            // except for up and down, which are always straight up and down
+            let forward = Self::forward(yaw, pitch);
+            let right = forward.cross(&matrix![0; 1; 0]).normalized();
+            let direction =
+                right * self.held.x() + matrix![0; 1; 0] * self.held.y() + forward * self.held.z();
+            self.held = matrix![0; 0; 0];
+            let wanted = if direction.length() > 0.0 {
+                direction.normalized() * self.speed
+            } else {
+                matrix![0; 0; 0]
+            };
+            self.velocity = self.velocity + (wanted - self.velocity) * t;
+            self.position = self.position + self.velocity * dt;
+        }
+
+        fn apply(&self, camera: &mut Camera) {
+            let (yaw, pitch) = self.current.unwrap_or((self.yaw, self.pitch));
+            camera.set_position(self.position);
+            camera.set_target(self.position + Self::forward(yaw, pitch));
+        }
+
+        fn action_pressed(&mut self, action: Action) {
+            match action {
+                Action::SpeedUp => self.change_speed(1),
+                Action::SlowDown => self.change_speed(-1),
+                _ => {
+                    if let Some(view) = View::from_action(action) {
+                        self.snap(view);
+                    }
+                }
+            }
+        }
+
+        fn action_down(&mut self, action: Action) {
+            let direction = match action {
+                Action::MoveRight => matrix![1; 0; 0],
+                Action::MoveLeft => matrix![-1; 0; 0],
+                Action::MoveUp => matrix![0; 1; 0],
+                Action::MoveDown => matrix![0; -1; 0],
+                Action::MoveForward => matrix![0; 0; 1],
+                Action::MoveBack => matrix![0; 0; -1],
+                Action::TurnLeft => {
+                    self.turning.0 += 1.0;
+                    return;
+                }
+                Action::TurnRight => {
+                    self.turning.0 -= 1.0;
+                    return;
+                }
+                Action::TurnUp => {
+                    self.turning.1 += 1.0;
+                    return;
+                }
+                Action::TurnDown => {
+                    self.turning.1 -= 1.0;
+                    return;
+                }
+                _ => return,
+            };
+            self.held = self.held + direction;
+        }
+
+        fn mouse_scrolled(&mut self, amount: i32) {
+            self.change_speed(amount);
+        }
+    }
+
+    /// Turns a camera around a target on its own, like a model on a turntable.
+    /// The pause action stops and starts it, turning left and right turns it by hand,
+    /// and turning up and down raises and lowers the camera.
+    /// Snapping to a view pauses the turntable there.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct TurntableController {
+        pub target: Vec3,
+        pub distance: f32,
+        /// Radians above the target
+        pub pitch: f32,
+        /// Radians turned per second
+        pub speed: f32,
+        /// Radians turned per second while a turning key is held
+        pub turn_speed: f32,
+        pub paused: bool,
+        /// Roughly how many seconds the camera takes to start and stop turning and to
+        /// finish snapping, or 0 to follow the turntable exactly
+        pub smoothing: f32,
+        /// Radians around the y axis, where 0 looks down the negative z axis
+        angle: f32,
+        /// Turning and raising directions from the keys held this frame
+        turning: (f32, f32),
+        /// Angle and pitch while the camera catches up, if it's smoothed
+        current: Option<(f32, f32)>,
+    }
+
+    impl TurntableController {
+        pub fn new(target: Vec3, distance: f32) -> Self {
+            Self {
+                target,
+                distance,
+                pitch: 0.0,
+                speed: 0.5,
+                turn_speed: 1.5,
+                paused: false,
+                smoothing: 0.0,
+                angle: 0.0,
+                turning: (0.0, 0.0),
+                current: None,
+            }
+        }
+
+        /// A controller which starts turning from wherever the camera is
+        pub fn from_camera(camera: &Camera) -> Self {
+            let mut controller = Self::new(camera.target(), 0.0);
+            controller.reset(camera);
+            controller
+        }
+
+        pub fn with_speed(mut self, speed: f32) -> Self {
+            self.speed = speed;
+            self
+        }
+
+        pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+            self.smoothing = smoothing;
+            self
+        }
+
+        /// Radians around the y axis the turntable has reached
+        pub fn angle(&self) -> f32 {
+            self.angle
+        }
+    }
+
+    impl CameraController for TurntableController {
+        fn reset(&mut self, camera: &Camera) {
+            let offset = camera.position() - camera.target();
+            (self.angle, self.pitch) = orbit_angles(offset);
+            self.target = camera.target();
+            self.distance = offset.length();
+            self.current = (self.smoothing > 0.0).then_some((self.angle, self.pitch));
+        }
+
+        fn snap(&mut self, view: View) {
+            let (angle, pitch) = view.angles();
+            self.angle = nearest_angle(self.current.map_or(self.angle, |x| x.0), angle);
+            self.pitch = pitch;
+            self.paused = true;
+        }
+
+        fn update(&mut self, dt: f32) {
+            let speed = if self.paused { 0.0 } else { self.speed };
+            self.angle += (speed + self.turning.0 * self.turn_speed) * dt;
+            self.pitch =
+                (self.pitch + self.turning.1 * self.turn_speed * dt).clamp(-MAX_PITCH, MAX_PITCH);
+            self.turning = (0.0, 0.0);
+
+            // following behind the turntable makes it ease in and out of turning
+            self.current = match self.current {
+                Some((angle, pitch)) if self.smoothing > 0.0 => {
+                    let t = catch_up(dt, self.smoothing);
+                    Some((
+                        angle + (self.angle - angle) * t,
+                        pitch + (self.pitch - pitch) * t,
+                    ))
+                }
+                None if self.smoothing > 0.0 => Some((self.angle, self.pitch)),
+                _ => None,
+            };
+        }
+
+        fn apply(&self, camera: &mut Camera) {
+            let (angle, pitch) = self.current.unwrap_or((self.angle, self.pitch));
+            camera.set_position(orbit_position(self.target, self.distance, angle, pitch));
+            camera.set_target(self.target);
+        }
+
+        fn action_pressed(&mut self, action: Action) {
+            if action == Action::Pause {
+                self.paused = !self.paused;
+            } else if let Some(view) = View::from_action(action) {
+                self.snap(view);
+            }
+        }
+
+        fn action_down(&mut self, action: Action) {
+            match action {
+                Action::TurnLeft => self.turning.0 -= 1.0,
+                Action::TurnRight => self.turning.0 += 1.0,
+                Action::TurnUp => self.turning.1 += 1.0,
+                Action::TurnDown => self.turning.1 -= 1.0,
+                _ => {}
+            }
+        }
+    }
+}
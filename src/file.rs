@@ -0,0 +1,37 @@
+pub mod file {
+    /// Why a text file couldn't be read, for the formats which are read line by line
+    #[derive(Debug)]
+    pub enum FileError {
+        Io(std::io::Error),
+        /// Something on the given line (counting from 1) couldn't be understood
+        Parse {
+            line: usize,
+            message: String,
+        },
+    }
+
+    impl From<std::io::Error> for FileError {
+        fn from(value: std::io::Error) -> Self {
+            FileError::Io(value)
+        }
+    }
+
+    /// Lines without their comments, numbered from 1, skipping any left blank.
+    /// Comments start at `#`, except on lines starting with one of the words in `literal`,
+    /// which can contain it.
+    pub fn lines<'a>(
+        source: &'a str,
+        literal: &'a [&str],
+    ) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+        source
+            .lines()
+            .map(|line| match line.split_whitespace().next() {
+                Some(word) if literal.contains(&word) => line,
+                _ => line.split('#').next().unwrap_or_default(),
+            })
+            .map(str::trim)
+            .enumerate()
+            .map(|(i, x)| (i + 1, x))
+            .filter(|(_, x)| !x.is_empty())
+    }
+}
@@ -0,0 +1,256 @@
+pub mod input {
+    use std::{
+        io::stdout,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc::{self, Receiver, Sender},
+            Arc, Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    };
+
+    use crossterm::event::{
+        self as terminal_event, DisableMouseCapture, EnableMouseCapture, Event,
+    };
+    use device_query::{DeviceQuery, DeviceState, Keycode};
+    use ruscii::keyboard::Key;
+
+    use crate::screen::screen::{Input, MouseEvent};
+
+    /// How often the keyboard is checked, and how long the terminal is waited on before
+    /// checking whether to stop
+    const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Keys only count as pressed if the terminal got some input within this long of them
+    /// going down, since the keyboard is read directly and may be typing into another window
+    const FOCUS_DELAY: Duration = Duration::from_millis(20);
+
+    enum Message {
+        Pressed(Key),
+        Released(Key),
+        Mouse(MouseEvent),
+    }
+
+    /// Reads the keyboard and mouse on threads of its own, for a screen to collect once a frame.
+    ///
+    /// Terminals don't usually say when keys are released, so keys come from the keyboard
+    /// itself, as in ruscii. ruscii's keyboard also reads the terminal's input to tell whether
+    /// it has focus, throwing mouse events away, so this reads it in one place for both.
+    pub struct TerminalInput {
+        running: Arc<AtomicBool>,
+        threads: Vec<JoinHandle<()>>,
+        receiver: Receiver<Message>,
+        /// Keys which are down, in the order they were pressed
+        held: Vec<Key>,
+    }
+
+    impl TerminalInput {
+        /// Starts reading, and turns on mouse reporting if the terminal has it
+        pub fn new() -> Self {
+            let _ = crossterm::execute!(stdout(), EnableMouseCapture);
+            let running = Arc::new(AtomicBool::new(true));
+            let last_input = Arc::new(Mutex::new(None));
+            let (sender, receiver) = mpsc::channel();
+
+            let terminal = {
+                let (running, last_input, sender) =
+                    (running.clone(), last_input.clone(), sender.clone());
+                thread::spawn(move || read_terminal(&running, &last_input, &sender))
+            };
+            let keyboard = {
+                let running = running.clone();
+                thread::spawn(move || read_keyboard(&running, &last_input, &sender))
+            };
+            Self {
+                running,
+                threads: vec![terminal, keyboard],
+                receiver,
+                held: Vec::new(),
+            }
+        }
+
+        /// Everything since the last call, in the order it happened,
+        /// followed by every key which is held down
+        pub fn inputs(&mut self) -> Vec<Input> {
+            let mut inputs = Vec::new();
+            for message in self.receiver.try_iter() {
+                match message {
+                    Message::Pressed(key) if !self.held.contains(&key) => {
+                        self.held.push(key);
+                        inputs.push(Input::Pressed(key));
+                    }
+                    Message::Released(key) if self.held.contains(&key) => {
+                        self.held.retain(|&x| x != key);
+                        inputs.push(Input::Released(key));
+                    }
+                    Message::Mouse(event) => inputs.push(Input::Mouse(event)),
+                    _ => {}
+                }
+            }
+            inputs.extend(self.held.iter().map(|&x| Input::Down(x)));
+            inputs
+        }
+    }
+
+    impl Default for TerminalInput {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Drop for TerminalInput {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+            for thread in self.threads.drain(..) {
+                // the keyboard thread panics without a keyboard to read, such as with no X display
+                let _ = thread.join();
+            }
+            let _ = crossterm::execute!(stdout(), DisableMouseCapture);
+        }
+    }
+
+    /// Passes on mouse events, and notes when the terminal last got any input
+    fn read_terminal(
+        running: &AtomicBool,
+        last_input: &Mutex<Option<Instant>>,
+        sender: &Sender<Message>,
+    ) {
+        while running.load(Ordering::SeqCst) {
+            match terminal_event::poll(POLL_INTERVAL) {
+                Ok(true) => {
+                    let event = terminal_event::read();
+                    *last_input.lock().unwrap() = Some(Instant::now());
+                    if let Ok(Event::Mouse(event)) = event {
+                        let _ = sender.send(Message::Mouse(MouseEvent::from_terminal(event)));
+                    }
+                }
+                Ok(false) => {}
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Passes on keys going down while the terminal has focus, and every key coming up
+    fn read_keyboard(
+        running: &AtomicBool,
+        last_input: &Mutex<Option<Instant>>,
+        sender: &Sender<Message>,
+    ) {
+        let device = DeviceState::new();
+        let mut last_keys = Vec::new();
+        // keys which went down without any terminal input yet, and when
+        let mut pending: Vec<(Key, Instant)> = Vec::new();
+        while running.load(Ordering::SeqCst) {
+            thread::sleep(POLL_INTERVAL);
+            let keys = device.get_keys();
+            let now = Instant::now();
+            for key in keys.iter().filter(|x| !last_keys.contains(*x)) {
+                if let Some(key) = key_from_device(key) {
+                    pending.push((key, now));
+                }
+            }
+            for key in last_keys.iter().filter(|x| !keys.contains(*x)) {
+                if let Some(key) = key_from_device(key) {
+                    // a press which never counted has nothing to release
+                    pending.retain(|x| x.0 != key);
+                    let _ = sender.send(Message::Released(key));
+                }
+            }
+            let last_input = *last_input.lock().unwrap();
+            pending.retain(|&(key, time)| {
+                let focused = last_input.is_some_and(|x| {
+                    x.saturating_duration_since(time)
+                        .max(time.saturating_duration_since(x))
+                        <= FOCUS_DELAY
+                });
+                if focused {
+                    let _ = sender.send(Message::Pressed(key));
+                }
+                !focused && now - time <= FOCUS_DELAY
+            });
+            last_keys = keys;
+        }
+    }
+
+    fn key_from_device(key: &Keycode) -> Option<Key> {
+        Some(match key {
+            Keycode::Escape => Key::Esc,
+            Keycode::Space => Key::Space,
+            Keycode::Enter => Key::Enter,
+            Keycode::Backspace => Key::Backspace,
+            Keycode::CapsLock => Key::CapsLock,
+            Keycode::Tab => Key::Tab,
+            Keycode::Up => Key::Up,
+            Keycode::Down => Key::Down,
+            Keycode::Left => Key::Left,
+            Keycode::Right => Key::Right,
+            Keycode::Home => Key::Home,
+            Keycode::End => Key::End,
+            Keycode::PageUp => Key::PageUp,
+            Keycode::PageDown => Key::PageDown,
+            Keycode::Insert => Key::Insert,
+            Keycode::Delete => Key::Delete,
+            Keycode::A => Key::A,
+            Keycode::B => Key::B,
+            Keycode::C => Key::C,
+            Keycode::D => Key::D,
+            Keycode::E => Key::E,
+            Keycode::F => Key::F,
+            Keycode::G => Key::G,
+            Keycode::H => Key::H,
+            Keycode::I => Key::I,
+            Keycode::J => Key::J,
+            Keycode::K => Key::K,
+            Keycode::L => Key::L,
+            Keycode::M => Key::M,
+            Keycode::N => Key::N,
+            Keycode::O => Key::O,
+            Keycode::P => Key::P,
+            Keycode::Q => Key::Q,
+            Keycode::R => Key::R,
+            Keycode::S => Key::S,
+            Keycode::T => Key::T,
+            Keycode::U => Key::U,
+            Keycode::V => Key::V,
+            Keycode::W => Key::W,
+            Keycode::X => Key::X,
+            Keycode::Y => Key::Y,
+            Keycode::Z => Key::Z,
+            Keycode::Key0 => Key::Num0,
+            Keycode::Key1 => Key::Num1,
+            Keycode::Key2 => Key::Num2,
+            Keycode::Key3 => Key::Num3,
+            Keycode::Key4 => Key::Num4,
+            Keycode::Key5 => Key::Num5,
+            Keycode::Key6 => Key::Num6,
+            Keycode::Key7 => Key::Num7,
+            Keycode::Key8 => Key::Num8,
+            Keycode::Key9 => Key::Num9,
+            Keycode::F1 => Key::F1,
+            Keycode::F2 => Key::F2,
+            Keycode::F3 => Key::F3,
+            Keycode::F4 => Key::F4,
+            Keycode::F5 => Key::F5,
+            Keycode::F6 => Key::F6,
+            Keycode::F7 => Key::F7,
+            Keycode::F8 => Key::F8,
+            Keycode::F9 => Key::F9,
+            Keycode::F10 => Key::F10,
+            Keycode::F11 => Key::F11,
+            Keycode::F12 => Key::F12,
+            Keycode::Grave => Key::Grave,
+            Keycode::Minus => Key::Minus,
+            Keycode::Equal => Key::Equal,
+            Keycode::LeftBracket => Key::LeftBracket,
+            Keycode::RightBracket => Key::RightBracket,
+            Keycode::BackSlash => Key::BackSlash,
+            Keycode::Semicolon => Key::Semicolon,
+            Keycode::Apostrophe => Key::Apostrophe,
+            Keycode::Comma => Key::Comma,
+            Keycode::Dot => Key::Dot,
+            Keycode::Slash => Key::Slash,
+            _ => return None,
+        })
+    }
+}
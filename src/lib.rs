@@ -0,0 +1,29 @@
+// every module is wrapped in a module of the same name, like `matrix::matrix`
+#![allow(clippy::module_inception)]
+
+#[macro_use]
+pub mod matrix;
+pub mod animation;
+pub mod bindings;
+pub mod camera;
+pub mod collision;
+pub mod colour;
+pub mod controller;
+pub mod file;
+pub mod input;
+pub mod light;
+pub mod material;
+pub mod obj;
+pub mod pathtrace;
+pub mod raytrace;
+pub mod recording;
+pub mod render;
+pub mod scene;
+pub mod screen;
+pub mod sdf;
+pub mod shadow;
+pub mod shapes;
+pub mod skeleton;
+pub mod snapshot;
+pub mod stack;
+pub mod texture;
@@ -0,0 +1,290 @@
+pub mod light {
+    use crate::{
+        colour::colour::Colour,
+        matrix::matrix::{Matrix, Vec3},
+    };
+
+    /// Points closer than this to a point or spot light have no direction to it
+    const MIN_DISTANCE: f32 = 1e-6;
+
+    /// How quickly a light fades with distance `d`, as `1 / (constant + linear d + quadratic d^2)`
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Attenuation {
+        pub constant: f32,
+        pub linear: f32,
+        pub quadratic: f32,
+    }
+
+    impl Attenuation {
+        pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+            Self {
+                constant,
+                linear,
+                quadratic,
+            }
+        }
+
+        /// No fading at all
+        pub fn none() -> Self {
+            Self::new(1.0, 0.0, 0.0)
+        }
+
+        pub fn at(&self, distance: f32) -> f32 {
+            1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+                .max(f32::EPSILON)
+        }
+    }
+
+    impl Default for Attenuation {
+        /// Fades to about a tenth of its brightness 10 units away
+        fn default() -> Self {
+            Self::new(1.0, 0.1, 0.08)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum LightKind {
+        /// Lights everything equally, from every direction
+        Ambient,
+        /// Infinitely far away, like the sun
+        Directional {
+            /// The direction light travels in
+            direction: Vec3,
+        },
+        /// Shines in every direction from a point
+        Point {
+            position: Vec3,
+            attenuation: Attenuation,
+        },
+        /// Shines in a cone from a point. Inside `inner_angle` from `direction` the light is at
+        /// full strength, fading out to nothing at `outer_angle`. Angles are in radians.
+        Spot {
+            position: Vec3,
+            direction: Vec3,
+            inner_angle: f32,
+            outer_angle: f32,
+            attenuation: Attenuation,
+        },
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Light {
+        pub kind: LightKind,
+        pub colour: Colour,
+        pub intensity: f32,
+    }
+
+    impl Light {
+        pub fn new(kind: LightKind, colour: Colour, intensity: f32) -> Self {
+            Self {
+                kind,
+                colour,
+                intensity,
+            }
+        }
+
+        pub fn ambient(colour: Colour, intensity: f32) -> Self {
+            Self::new(LightKind::Ambient, colour, intensity)
+        }
+
+        /// A light shining along `direction`, which mustn't be zero
+        pub fn directional(direction: Vec3, colour: Colour, intensity: f32) -> Self {
+            assert!(
+                direction.length() > 0.0,
+                "directional light needs a direction, not {direction:?}"
+            );
+            let direction = direction.normalized();
+            Self::new(LightKind::Directional { direction }, colour, intensity)
+        }
+
+        pub fn point(position: Vec3, colour: Colour, intensity: f32) -> Self {
+            let attenuation = Attenuation::default();
+            Self::new(
+                LightKind::Point {
+                    position,
+                    attenuation,
+                },
+                colour,
+                intensity,
+            )
+        }
+
+        /// A light at `position` shining along `direction`, which mustn't be zero
+        pub fn spot(
+            position: Vec3,
+            direction: Vec3,
+            inner_angle: f32,
+            outer_angle: f32,
+            colour: Colour,
+            intensity: f32,
+        ) -> Self {
+            assert!(
+                direction.length() > 0.0,
+                "spot light needs a direction, not {direction:?}"
+            );
+            Self::new(
+                LightKind::Spot {
+                    position,
+                    direction: direction.normalized(),
+                    inner_angle,
+                    outer_angle,
+                    attenuation: Attenuation::default(),
+                },
+                colour,
+                intensity,
+            )
+        }
+
+        /// Changes how a point or spot light fades with distance
+        pub fn with_attenuation(mut self, value: Attenuation) -> Self {
+            if let LightKind::Point { attenuation, .. } | LightKind::Spot { attenuation, .. } =
+                &mut self.kind
+            {
+                *attenuation = value;
+            }
+            self
+        }
+
+        /// The light moved by a world matrix, such as a scene node's
+        pub fn transformed(&self, matrix: &Matrix<4, 4>) -> Self {
+            let mut light = *self;
+            match &mut light.kind {
+                LightKind::Ambient => {}
+                LightKind::Directional { direction } => {
+                    *direction = matrix.transform_vector(*direction).normalized();
+                }
+                LightKind::Point { position, .. } => *position = matrix.transform_point(*position),
+                LightKind::Spot {
+                    position,
+                    direction,
+                    ..
+                } => {
+                    *position = matrix.transform_point(*position);
+                    *direction = matrix.transform_vector(*direction).normalized();
+                }
+            }
+            light
+        }
+
+        /// How far the light is from `point`, which is infinite for lights without a position
+        pub fn distance(&self, point: Vec3) -> f32 {
+            match self.kind {
+                LightKind::Point { position, .. } | LightKind::Spot { position, .. } => {
+                    (position - point).length()
+                }
+                LightKind::Ambient | LightKind::Directional { .. } => f32::INFINITY,
+            }
+        }
+
+        /// The unit vector from `point` towards the light, and the light reaching the point,
+        /// or `None` for ambient lights, and for point and spot lights at `point` itself
+        pub fn incident(&self, point: Vec3) -> Option<(Vec3, Colour)> {
+            let radiance = self.colour * self.intensity;
+            match self.kind {
+                LightKind::Ambient => None,
+                LightKind::Directional { direction } => Some((-direction, radiance)),
+                LightKind::Point {
+                    position,
+                    attenuation,
+                } => {
+                    let offset = position - point;
+                    let distance = offset.length();
+                    if distance < MIN_DISTANCE {
+                        return None;
+                    }
+                    Some((offset / distance, radiance * attenuation.at(distance)))
+                }
+                LightKind::Spot {
+                    position,
+                    direction,
+                    inner_angle,
+                    outer_angle,
+                    attenuation,
+                } => {
+                    let offset = position - point;
+                    let distance = offset.length();
+                    if distance < MIN_DISTANCE {
+                        return None;
+                    }
+                    let to_light = offset / distance;
+                    let cone = smoothstep(
+                        outer_angle.cos(),
+                        inner_angle.cos(),
+                        (-to_light).dot(&direction),
+                    );
+                    Some((to_light, radiance * (attenuation.at(distance) * cone)))
+                }
+            }
+        }
+    }
+
+    /// 0 below `edge0`, 1 above `edge1`, and a smooth curve between them
+    fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+        if edge0 >= edge1 {
+            return if x >= edge1 { 1.0 } else { 0.0 };
+        }
+        let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// The light a surface receives, before it's multiplied with the surface's colours
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Lighting {
+        /// Ambient and diffuse light, for the surface's diffuse colour
+        pub diffuse: Colour,
+        /// Blinn-Phong highlights, for the surface's specular colour
+        pub specular: Colour,
+    }
+
+    /// Adds up every light's contribution at `point`, which has the unit normal `normal`.
+    /// `to_camera` is the unit vector from the point towards the viewer.
+    pub fn illuminate(
+        lights: &[Light],
+        point: Vec3,
+        normal: Vec3,
+        to_camera: Vec3,
+        shininess: f32,
+    ) -> Lighting {
+        illuminate_with(lights, point, normal, to_camera, shininess, |_| 1.0)
+    }
+
+    /// Like `illuminate`, but `visibility` gives how much of the light at each index
+    /// reaches the point, from 0 where it's in shadow to 1.
+    /// For ambient lights, this is how much the surroundings are occluded.
+    pub fn illuminate_with(
+        lights: &[Light],
+        point: Vec3,
+        normal: Vec3,
+        to_camera: Vec3,
+        shininess: f32,
+        mut visibility: impl FnMut(usize) -> f32,
+    ) -> Lighting {
+        let mut lighting = Lighting {
+            diffuse: Colour::BLACK,
+            specular: Colour::BLACK,
+        };
+        for (index, light) in lights.iter().enumerate() {
+            if light.kind == LightKind::Ambient {
+                let occlusion = visibility(index);
+                lighting.diffuse = lighting.diffuse + light.colour * (light.intensity * occlusion);
+                continue;
+            }
+            let Some((to_light, radiance)) = light.incident(point) else {
+                continue;
+            };
+            let diffuse = normal.dot(&to_light);
+            if diffuse <= 0.0 {
+                continue;
+            }
+            let radiance = radiance * visibility(index);
+            if radiance.max_component() <= 0.0 {
+                continue;
+            }
+            let half = (to_light + to_camera).normalized();
+            let specular = normal.dot(&half).max(0.0).powf(shininess);
+            lighting.diffuse = lighting.diffuse + radiance * diffuse;
+            lighting.specular = lighting.specular + radiance * specular;
+        }
+        lighting
+    }
+}
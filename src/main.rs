@@ -1,51 +1,466 @@
-use ruscii::keyboard::Key;
-use screen::screen::{GameState, Screen};
+use std::cell::RefCell;
 
-#[macro_use]
-mod matrix;
-mod collision;
-mod screen;
-mod shapes;
+use bindings::bindings::{key_name, Action, Bindings};
+use camera::camera::Camera;
+use colour::colour::Colour;
+use controller::controller::{
+    CameraController, FreeFlyController, OrbitController, TurntableController,
+};
+use light::light::Light;
+use material::material::Material;
+use matrix::matrix::Matrix;
+use pathtrace::pathtrace::PathTracer;
+use raytrace::raytrace::{RayTracer, TraceScene};
+use recording::recording::Recording;
+use render::render::Renderer;
+use ruscii::{keyboard::Key, terminal::Color};
+use screen::screen::{Context, Framebuffer, MouseButton, MouseEvent, MouseEventKind, Screen};
+use shadow::shadow::ShadowSettings;
+use shapes::shapes::{Mesh, Transformation};
+use stack::stack::{Scene, SceneStack, Transition};
+use texture::texture::ImageError;
+
+use render3d::{
+    bindings, camera, colour, controller, light, material, matrix, pathtrace, raytrace, recording,
+    render, screen, shadow, shapes, stack, texture,
+};
 
 fn main() {
-    let mut screen = Screen::new(State::new());
+    // `--raytrace out.ppm` or `--pathtrace out.ppm` renders a still image
+    // instead of running in the terminal, and `--bindings keys.txt` rebinds keys.
+    // `--record input.txt` saves the input when the program ends,
+    // for `--replay input.txt` to play back.
+    let args: Vec<String> = std::env::args().collect();
+    let mut bindings = Bindings::new();
+    let (mut record_to, mut replay) = (None, None);
+    if let [_, flag, path] = args.as_slice() {
+        if flag == "--raytrace" || flag == "--pathtrace" {
+            let result = Viewer::new(bindings).render_still(path, 640, 480, flag == "--pathtrace");
+            if let Err(error) = result {
+                eprintln!("couldn't save {path}: {error:?}");
+            }
+            return;
+        }
+        if flag == "--bindings" {
+            match Bindings::load(path) {
+                Ok(loaded) => bindings = loaded,
+                Err(error) => {
+                    eprintln!("couldn't load {path}: {error:?}");
+                    return;
+                }
+            }
+        }
+        if flag == "--record" {
+            record_to = Some(path);
+        }
+        if flag == "--replay" {
+            match Recording::load(path) {
+                Ok(recording) => replay = Some(recording),
+                Err(error) => {
+                    eprintln!("couldn't load {path}: {error:?}");
+                    return;
+                }
+            }
+        }
+    }
+
+    let mut screen = Screen::new(SceneStack::new(Menu::new(bindings)));
+    if record_to.is_some() {
+        screen = screen.with_recording();
+    }
+    if let Some(recording) = replay {
+        screen = screen.with_replay(recording);
+    }
     screen.run();
+    if let (Some(path), Some(recording)) = (record_to, screen.recording()) {
+        if let Err(error) = recording.save(path) {
+            eprintln!("couldn't save {path}: {error:?}");
+        }
+    }
+}
+
+/// Shows a spinning model above the ground, which the camera controllers move around
+struct Viewer {
+    mesh: Mesh,
+    /// Where the model's shadow falls
+    ground: Mesh,
+    camera: Camera,
+    bindings: Bindings,
+    /// `Action::NextController` switches between these
+    controllers: Vec<Box<dyn CameraController>>,
+    controller: usize,
+    /// Set while `Action::PathTrace` has the view path traced instead of rasterised.
+    /// It keeps adding samples for as long as the camera stays still.
+    path_tracer: Option<RefCell<PathTracer>>,
 }
 
-struct State {}
+impl Viewer {
+    fn new(bindings: Bindings) -> Self {
+        let mut mesh = Mesh::cuboid(matrix![2; 2; 2]);
+        mesh.set_material(Material::new(Colour::new(1.0, 0.6, 0.2)));
+        let mut camera = Camera::new(matrix![0; 1.5; 5], matrix![0; 0; 0]);
+        // fit the whole cube in any window at least as wide as it looks tall,
+        // which is two cells across for each one down
+        if let Some(sphere) = mesh.bounding_sphere() {
+            camera.frame(&sphere, 2, 1);
+        }
+        // just below the cube's corners however it turns
+        let mut ground = Mesh::cuboid(matrix![12; 0.2; 12]);
+        ground.set_transformation(Transformation::translation(matrix![0; -1.9; 0]));
+        Self {
+            mesh,
+            ground,
+            camera,
+            bindings,
+            controllers: vec![
+                Box::new(OrbitController::from_camera(&camera)),
+                Box::new(FreeFlyController::from_camera(&camera).with_smoothing(0.2)),
+                Box::new(TurntableController::from_camera(&camera).with_smoothing(0.5)),
+            ],
+            controller: 0,
+            path_tracer: None,
+        }
+    }
+
+    /// Lit from above and to the right, so the cube's shadow falls where it can be seen
+    fn lights() -> Vec<Light> {
+        vec![
+            Light::ambient(Colour::WHITE, 0.15),
+            Light::directional(matrix![-3; -4; -2], Colour::WHITE, 0.85),
+        ]
+    }
+
+    fn path_tracer() -> PathTracer {
+        let mut path_tracer = PathTracer::new();
+        path_tracer.background = Colour::grey(0.15);
+        path_tracer
+    }
+
+    fn controller(&mut self) -> &mut dyn CameraController {
+        self.controllers[self.controller].as_mut()
+    }
+
+    fn trace_scene(&self) -> TraceScene {
+        let mut scene = TraceScene::new();
+        scene.add_mesh(&self.mesh, &Matrix::identity());
+        scene.add_mesh(&self.ground, &Matrix::identity());
+        scene.lights = Viewer::lights();
+        scene
+    }
 
-impl State {
-    fn new() -> Self {
-        Self {}
+    fn render_still(
+        &self,
+        path: &str,
+        width: usize,
+        height: usize,
+        path_traced: bool,
+    ) -> Result<(), ImageError> {
+        let scene = self.trace_scene();
+        // image pixels are square, unlike terminal cells
+        let mut camera = self.camera;
+        camera.set_cell_aspect(1.0);
+        let image = if path_traced {
+            let mut path_tracer = Viewer::path_tracer();
+            path_tracer.samples_per_frame = 16;
+            path_tracer.render(&scene, &camera, width, height)
+        } else {
+            RayTracer::new().render(&scene, &camera, width, height)
+        };
+        image.save(path)
     }
 }
 
-impl GameState for State {
-    fn update(&mut self, app_state: &mut ruscii::app::State) {}
+impl Scene for Viewer {
+    fn update(&mut self, dt: f32, _context: &mut Context) -> Transition {
+        // the path tracer can only keep refining while the scene stays still
+        if self.path_tracer.is_none() {
+            let mut transformation = *self.mesh.transformation();
+            transformation.rotation = transformation.rotation + matrix![0.33; 0.69; 0] * dt;
+            self.mesh.set_transformation(transformation);
+        }
+        self.controller().update(dt);
+        self.controllers[self.controller].apply(&mut self.camera);
+        Transition::Stay
+    }
+
+    fn draw(&self, framebuffer: &mut Framebuffer) {
+        let mut renderer = Renderer::new();
+        if let Some(path_tracer) = &self.path_tracer {
+            let (width, height) = (framebuffer.width(), framebuffer.height());
+            let image =
+                path_tracer
+                    .borrow_mut()
+                    .render(&self.trace_scene(), &self.camera, width, height);
+            renderer.draw_image(framebuffer, &image);
+            return;
+        }
+        renderer.lights = Viewer::lights();
+        renderer.shadows = Some(ShadowSettings::default());
+        let world = Matrix::identity();
+        renderer.cast_shadows(&[(&self.mesh, world)]);
+        self.ground.draw(&mut renderer, framebuffer, &self.camera);
+        self.mesh.draw(&mut renderer, framebuffer, &self.camera);
+    }
+
+    fn key_pressed(&mut self, key: Key, _context: &mut Context) -> Transition {
+        for action in self.bindings.actions(key) {
+            match action {
+                Action::Help => return Transition::Push(Box::new(Help::new(&self.bindings))),
+                Action::NextController => {
+                    // the next controller carries on from wherever this one left the camera
+                    self.controller = (self.controller + 1) % self.controllers.len();
+                    let camera = self.camera;
+                    self.controller().reset(&camera);
+                }
+                Action::PathTrace => {
+                    if self.path_tracer.take().is_none() {
+                        self.path_tracer = Some(RefCell::new(Viewer::path_tracer()));
+                    }
+                }
+                _ => self.controller().action_pressed(action),
+            }
+        }
+        Transition::Stay
+    }
+
+    fn key_released(&mut self, key: Key, _context: &mut Context) -> Transition {
+        if self.bindings.matches(Action::Quit, key) {
+            Transition::Pop
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn key_down(&mut self, key: Key, _context: &mut Context) -> Transition {
+        for action in self.bindings.actions(key) {
+            self.controller().action_down(action);
+        }
+        Transition::Stay
+    }
 
-    fn draw(&self, pencil: &mut ruscii::drawing::Pencil, win_x: usize, win_y: usize) {
-        pencil.draw_hline('#', ruscii::spatial::Vec2 { x: 5, y: 10 }, 8);
+    fn mouse(&mut self, event: MouseEvent, _context: &mut Context) -> Transition {
+        let position = event.position;
+        match event.kind {
+            MouseEventKind::Down(button) => self.controller().mouse_down(button, position),
+            MouseEventKind::Up(button) => self.controller().mouse_up(button, position),
+            MouseEventKind::Dragged(button) => {
+                self.controller()
+                    .mouse_dragged(button, position, event.modifiers)
+            }
+            MouseEventKind::Scrolled(amount) => self.controller().mouse_scrolled(amount),
+            MouseEventKind::Moved => {}
+        }
+        Transition::Stay
     }
+}
+
+const MENU_ITEMS: [&str; 3] = ["View model", "Help", "Quit"];
 
-    fn key_pressed(&mut self, key: Key, app_state: &mut ruscii::app::State) {}
+/// The first scene, which leads to everything else
+struct Menu {
+    bindings: Bindings,
+    selected: usize,
+    size: (usize, usize),
+}
 
-    fn key_released(&mut self, key: Key, app_state: &mut ruscii::app::State) {
-        match key {
-            Key::Esc => app_state.stop(),
-            _ => (),
+impl Menu {
+    fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            selected: 0,
+            size: (0, 0),
         }
     }
 
-    fn key_down(&mut self, key: Key, app_state: &mut ruscii::app::State) {}
+    /// The row of the first item, with the title two rows above
+    fn top(&self) -> usize {
+        (self.size.1 / 2).saturating_sub(1)
+    }
+
+    fn choose(&self) -> Transition {
+        match self.selected {
+            0 => Transition::Push(Box::new(Viewer::new(self.bindings.clone()))),
+            1 => Transition::Push(Box::new(Help::new(&self.bindings))),
+            _ => Transition::Pop,
+        }
+    }
+}
+
+impl Scene for Menu {
+    fn update(&mut self, _dt: f32, _context: &mut Context) -> Transition {
+        Transition::Stay
+    }
+
+    fn draw(&self, framebuffer: &mut Framebuffer) {
+        let left = (self.size.0 / 2).saturating_sub(7);
+        let top = self.top();
+        if let Some(title_row) = top.checked_sub(2) {
+            framebuffer.draw_text(left, title_row, "3D renderer", Color::White);
+        }
+        for (i, item) in MENU_ITEMS.iter().enumerate() {
+            let (marker, colour) = if i == self.selected {
+                ('>', Color::Yellow)
+            } else {
+                (' ', Color::Grey)
+            };
+            framebuffer.draw_text(left, top + i, &format!("{marker} {item}"), colour);
+        }
+    }
+
+    fn key_pressed(&mut self, key: Key, _context: &mut Context) -> Transition {
+        let count = MENU_ITEMS.len();
+        for action in self.bindings.actions(key) {
+            match action {
+                Action::MenuUp => self.selected = (self.selected + count - 1) % count,
+                Action::MenuDown => self.selected = (self.selected + 1) % count,
+                Action::Select => return self.choose(),
+                Action::Help => return Transition::Push(Box::new(Help::new(&self.bindings))),
+                _ => {}
+            }
+        }
+        Transition::Stay
+    }
+
+    fn key_released(&mut self, key: Key, _context: &mut Context) -> Transition {
+        if self.bindings.matches(Action::Quit, key) {
+            Transition::Pop
+        } else {
+            Transition::Stay
+        }
+    }
+
+    /// Clicking an item chooses it
+    fn mouse(&mut self, event: MouseEvent, _context: &mut Context) -> Transition {
+        let item = event.position.1.checked_sub(self.top());
+        match (event.kind, item) {
+            (MouseEventKind::Down(MouseButton::Left), Some(item)) if item < MENU_ITEMS.len() => {
+                self.selected = item;
+                self.choose()
+            }
+            _ => Transition::Stay,
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize, _context: &mut Context) {
+        self.size = (width, height);
+    }
+}
+
+/// A box listing the key bindings, over whatever was showing when it was opened
+struct Help {
+    lines: Vec<String>,
+    bindings: Bindings,
+}
+
+impl Help {
+    fn new(bindings: &Bindings) -> Self {
+        let mut lines = vec!["Keys".to_string(), String::new()];
+        for action in Action::ALL {
+            let keys: Vec<String> = bindings.keys(action).iter().map(|&x| key_name(x)).collect();
+            lines.push(format!("{:<16}{}", action.name(), keys.join(" ")));
+        }
+        Self {
+            lines,
+            bindings: bindings.clone(),
+        }
+    }
+}
+
+impl Scene for Help {
+    fn update(&mut self, _dt: f32, _context: &mut Context) -> Transition {
+        Transition::Stay
+    }
+
+    fn draw(&self, framebuffer: &mut Framebuffer) {
+        let width = self.lines.iter().map(|x| x.len()).max().unwrap_or(0) + 4;
+        let height = self.lines.len() + 2;
+        let left = framebuffer.width().saturating_sub(width) / 2;
+        let top = framebuffer.height().saturating_sub(height) / 2;
+        let edge = format!("+{}+", "-".repeat(width - 2));
+        framebuffer.draw_text(left, top, &edge, Color::White);
+        for (i, line) in self.lines.iter().enumerate() {
+            let row = format!("| {line:<0$} |", width - 4);
+            framebuffer.draw_text(left, top + 1 + i, &row, Color::White);
+        }
+        framebuffer.draw_text(left, top + height - 1, &edge, Color::White);
+    }
+
+    fn key_pressed(&mut self, key: Key, _context: &mut Context) -> Transition {
+        if self.bindings.matches(Action::Help, key) {
+            Transition::Pop
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn key_released(&mut self, key: Key, _context: &mut Context) -> Transition {
+        if self.bindings.matches(Action::Quit, key) {
+            Transition::Pop
+        } else {
+            Transition::Stay
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        collision::collision::{Collider, CollisionResult, CollisionTrait, LineCollider},
+    use std::{
+        cell::RefCell,
+        f32::consts::{PI, TAU},
+        rc::Rc,
+    };
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use ruscii::{
+        drawing::Pencil,
+        keyboard::Key,
+        terminal::{Canvas, Color, VisualElement},
+    };
+
+    use render3d::{
+        animation::animation::{
+            AnimationPlayer, Blend, Channel, Clip, Interpolation, PlaybackMode, Track,
+        },
+        bindings::bindings::{key_from_name, Action, Bindings},
+        camera::camera::Camera,
+        collision::collision::{Collider, CollisionResult, CollisionTrait, LineCollider, Ray},
+        colour::colour::{Colour, ColourMode},
+        controller::controller::{
+            CameraController, FreeFlyController, OrbitController, TurntableController, View,
+        },
+        file::file::FileError,
+        light::light::{illuminate, Attenuation, Light},
+        material::material::Material,
+        matrix,
         matrix::matrix::*,
+        obj::obj::{load_obj, parse_mtl, parse_obj, ObjError},
+        pathtrace::pathtrace::{cosine_hemisphere, PathTracer},
+        raytrace::raytrace::{RayTracer, TraceScene},
+        recording::recording::{RecordedFrame, Recording},
+        render::render::{glyph, rasterize_triangle, Renderer, GLYPH_RAMP},
+        scene::scene::SceneGraph,
+        screen::screen::{
+            Context, FixedTimestep, Framebuffer, GameState, HeadlessScreen, Input, Modifiers,
+            MouseButton, MouseEvent, MouseEventKind, MAX_FRAME_TIME,
+        },
+        sdf::sdf::{Raymarcher, Sdf},
+        shadow::shadow::ShadowSettings,
+        shapes::shapes::{
+            BoundingBox, BoundingSphere, Mesh, MorphTarget, Transformation, Triangle, Vertex,
+        },
+        skeleton::skeleton::Skeleton,
+        snapshot::snapshot::{check, check_within, diff, diff_within},
+        stack::stack::{Scene, SceneStack, Transition},
+        texture::texture::{Filter, Image, ImageError, Texture, Wrap},
     };
 
+    use crate::{Menu, Viewer};
+
     #[test]
     fn matrix_equality() {
         vec![2, 3, 4];
@@ -168,4 +583,2175 @@ mod tests {
         assert!(line1.is_parallel(&line1));
         assert!(!line1.is_parallel(&line2));
     }
+
+    fn assert_close<const H: usize, const W: usize>(
+        actual: Matrix<H, W>,
+        expected: Matrix<H, W>,
+        tolerance: f32,
+    ) {
+        let difference = actual - expected;
+        let within = difference
+            .values()
+            .iter()
+            .flatten()
+            .all(|x| x.abs() <= tolerance);
+        assert!(
+            within,
+            "{:?} should be within {} of {:?}",
+            actual, tolerance, expected
+        );
+    }
+
+    #[test]
+    fn cube_mass_properties() {
+        let mut cube = Mesh::cuboid(matrix![2; 2; 2]);
+        assert!((cube.surface_area() - 24.0).abs() < 1e-4);
+        assert!((cube.volume() - 8.0).abs() < 1e-4);
+        assert_close(cube.centre_of_mass().unwrap(), matrix![0; 0; 0], 1e-5);
+        assert_close(
+            cube.inertia_tensor().unwrap(),
+            Matrix::identity() * (8.0 * (4.0 + 4.0) / 12.0),
+            1e-4,
+        );
+
+        // stretched into a 4x2x2 box
+        cube.set_transformation(Transformation::scale(matrix![2; 1; 1]));
+        let mass = 16.0;
+        assert!((cube.surface_area() - 40.0).abs() < 1e-4);
+        assert!((cube.volume() - mass).abs() < 1e-4);
+        assert_close(cube.centre_of_mass().unwrap(), matrix![0; 0; 0], 1e-4);
+        assert_close(
+            cube.inertia_tensor().unwrap(),
+            matrix! {
+                mass * (4.0 + 4.0) / 12.0, 0, 0;
+                0, mass * (16.0 + 4.0) / 12.0, 0;
+                0, 0, mass * (16.0 + 4.0) / 12.0
+            },
+            1e-3,
+        );
+
+        // nothing to weigh without any volume, whether empty or squashed flat
+        assert_eq!(Mesh::new(vec![]).centre_of_mass(), None);
+        assert_eq!(Mesh::new(vec![]).inertia_tensor(), None);
+        cube.set_transformation(Transformation::scale(matrix![2; 0; 1]));
+        assert_eq!(cube.centre_of_mass(), None);
+        assert_eq!(cube.inertia_tensor(), None);
+    }
+
+    #[test]
+    fn sphere_mass_properties() {
+        let radius = 1.5;
+        let sphere = Mesh::uv_sphere(radius, 96, 48);
+        let volume = 4.0 / 3.0 * PI * radius.powi(3);
+        let area = 4.0 * PI * radius * radius;
+        assert!((sphere.surface_area() - area).abs() / area < 0.01);
+        assert!((sphere.volume() - volume).abs() / volume < 0.01);
+        assert_close(sphere.centre_of_mass().unwrap(), matrix![0; 0; 0], 1e-4);
+        let inertia = 0.4 * volume * radius * radius;
+        assert_close(
+            sphere.inertia_tensor().unwrap(),
+            Matrix::identity() * inertia,
+            inertia * 0.02,
+        );
+    }
+
+    #[test]
+    fn bounding_volumes() {
+        let mut cube = Mesh::cuboid(matrix![2; 4; 6]);
+        let expected = BoundingBox {
+            min: matrix![-1; -2; -3],
+            max: matrix![1; 2; 3],
+        };
+        assert_eq!(cube.local_bounding_box(), Some(expected));
+        assert_eq!(cube.bounding_box(), Some(expected));
+        let sphere = cube.local_bounding_sphere().unwrap();
+        assert_close(sphere.centre, matrix![0; 0; 0], 1e-6);
+        assert!((sphere.radius - 14f32.sqrt()).abs() < 1e-5);
+
+        cube.set_transformation(Transformation::scale(matrix![3; 1; 1]));
+        assert_eq!(cube.local_bounding_box(), Some(expected));
+        let bounds = cube.bounding_box().unwrap();
+        assert_close(bounds.min, matrix![-3; -2; -3], 1e-6);
+        assert_close(bounds.max, matrix![3; 2; 3], 1e-6);
+        assert!((cube.bounding_sphere().unwrap().radius - 22f32.sqrt()).abs() < 1e-5);
+
+        assert_eq!(Mesh::new(vec![]).bounding_box(), None);
+    }
+
+    #[test]
+    fn camera_framing() {
+        let sphere = Mesh::uv_sphere(3.0, 16, 8);
+        let (win_x, win_y) = (80, 24);
+        let mut camera = Camera::new(matrix![1; 2; 100], matrix![0; 0; 0]);
+        camera.frame(&sphere.bounding_sphere().unwrap(), win_x, win_y);
+
+        let radius = sphere.bounding_sphere().unwrap().radius;
+        for edge in [camera.right(), -camera.right(), camera.up(), -camera.up()] {
+            let point = camera.project(edge * radius, win_x, win_y).unwrap();
+            assert!(point.x() >= 0.0 && point.x() <= win_x as f32);
+            assert!(point.y() >= 0.0 && point.y() <= win_y as f32);
+        }
+        // the sphere's centre ends up in the middle of the window
+        assert_close(
+            camera.project(matrix![0; 0; 0], win_x, win_y).unwrap(),
+            matrix![40; 12; (camera.position()).length()],
+            1e-3,
+        );
+
+        let unit = BoundingSphere {
+            centre: matrix![0; 0; 0],
+            radius: 1.0,
+        };
+        camera.frame(&unit, win_x, win_y);
+        // the window is shorter than it is wide, so the sphere's silhouette touches the top edge
+        let half_fov = camera.fov() * 0.5;
+        let distance = camera.position().length();
+        let tangent = camera.position()
+            + (camera.forward() * half_fov.cos() + camera.up() * half_fov.sin())
+                * (distance * half_fov.cos());
+        let top = camera.project(tangent, win_x, win_y).unwrap();
+        assert!(top.y() >= 0.0 && top.y() < 1.0);
+
+        // a camera at its target, or looking straight down, still has a direction to frame along
+        let mut stacked = Camera::new(matrix![0; 0; 0], matrix![0; 0; 0]);
+        assert_close(stacked.forward(), matrix![0; 0; -1], 1e-6);
+        stacked.frame(&unit, win_x, win_y);
+        assert!(stacked.position().z() > 1.0);
+        let above = Camera::new(matrix![0; 5; 0], matrix![0; 0; 0]);
+        assert_close(above.right(), matrix![1; 0; 0], 1e-6);
+        assert!(above.project(matrix![0; 0; 0], win_x, win_y).is_some());
+        let mut camera = Camera::new(matrix![0; 0; 0], matrix![0; 0; 0]);
+        FreeFlyController::from_camera(&camera).apply(&mut camera);
+        OrbitController::from_camera(&camera).apply(&mut camera);
+        assert!(camera
+            .view_matrix()
+            .values()
+            .iter()
+            .flatten()
+            .all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn transformation_order() {
+        let quarter = PI / 2.0;
+        // scaled, then rotated about the origin, then moved
+        let transformation =
+            Transformation::new(matrix![10; 0; 0], matrix![0; 0; quarter], matrix![2; 1; 1]);
+        assert_close(
+            transformation.transform(matrix![1; 0; 0]),
+            matrix![10; 2; 0],
+            1e-5,
+        );
+
+        // rotating about a pivot leaves the pivot where it is
+        let pivoted = Transformation::rotation(matrix![0; 0; quarter]).with_pivot(matrix![1; 1; 0]);
+        assert_close(pivoted.transform(matrix![1; 1; 0]), matrix![1; 1; 0], 1e-5);
+        assert_close(pivoted.transform(matrix![2; 1; 0]), matrix![1; 2; 0], 1e-5);
+
+        let mut cube = Mesh::cuboid(matrix![1; 1; 1]);
+        cube.set_transformation(Transformation::translation(matrix![1; 2; 3]));
+        assert_close(cube.centre_of_mass().unwrap(), matrix![1; 2; 3], 1e-5);
+    }
+
+    #[test]
+    fn transformation_algebra() {
+        let a = Transformation::new(matrix![1; -2; 3], matrix![0.3; -0.2; 1.1], matrix![2; 2; 2])
+            .with_pivot(matrix![0.5; 0; -1]);
+        let b = Transformation::new(
+            matrix![-4; 0; 2],
+            matrix![-0.7; 0.4; 0.1],
+            matrix![1; 3; 0.5],
+        );
+        let points = [matrix![0; 0; 0], matrix![1; 2; 3], matrix![-3; 0.5; 2]];
+
+        for point in points {
+            let matrix = a.to_matrix();
+            assert_close(matrix.transform_point(point), a.transform(point), 1e-4);
+            assert_close(a.inverse_transform(a.transform(point)), point, 1e-4);
+            assert_close(b.inverse_transform(b.transform(point)), point, 1e-4);
+            assert_close(
+                a.inverse().unwrap().transform(a.transform(point)),
+                point,
+                1e-4,
+            );
+            assert_close(
+                (a * b).transform_point(point),
+                a.transform(b.transform(point)),
+                1e-3,
+            );
+            // a non-uniform scale followed by a rotation, twice over, needs shear
+            assert_close(
+                (b * b).transform_point(point),
+                b.transform(b.transform(point)),
+                1e-3,
+            );
+        }
+
+        let decomposed = Transformation::from_matrix(&b.to_matrix());
+        assert_close(decomposed.translation, b.translation, 1e-5);
+        assert_close(decomposed.rotation, b.rotation, 1e-5);
+        assert_close(decomposed.scale, b.scale, 1e-5);
+        assert_close(a * a.inverse().unwrap(), Matrix::identity(), 1e-4);
+        // undoing b would scale before rotating, which a transformation can't do
+        assert!(b.inverse().is_none());
+        let turned = Transformation::new(matrix![0; 0; 0], matrix![0; 0; 0.5], matrix![2; 1; 1]);
+        assert!(turned.inverse().is_none());
+        // but its matrix can be undone, and combined with more transformations
+        let point = matrix![1; 2; 3];
+        let undo = turned.to_matrix().inverse().unwrap();
+        assert_close(undo.transform_point(turned.transform(point)), point, 1e-4);
+        assert_close(
+            (turned * b * a).transform_point(point),
+            turned.transform(b.transform(a.transform(point))),
+            1e-3,
+        );
+        assert_close(
+            (a * (b * turned)).transform_point(point),
+            a.transform(b.transform(turned.transform(point))),
+            1e-3,
+        );
+        // without the rotation it can be undone
+        let stretched =
+            Transformation::new(matrix![-4; 0; 2], matrix![0; 0; 0], matrix![1; 3; 0.5]);
+        let inverse = stretched.inverse().unwrap();
+        assert_close(inverse.transform(stretched.transform(point)), point, 1e-4);
+        let flat = Transformation::new(matrix![0; 0; 0], matrix![0; 0; 0], matrix![1; 0; 1]);
+        assert!(flat.inverse().is_none());
+    }
+
+    #[test]
+    fn scene_graph_hierarchy() {
+        let mut scene = SceneGraph::new();
+        let sun = scene
+            .add(
+                None,
+                "sun",
+                Transformation::rotation(matrix![0; PI / 2.0; 0]),
+                None,
+            )
+            .unwrap();
+        let earth = scene
+            .add(
+                Some(sun),
+                "earth",
+                Transformation::translation(matrix![10; 0; 0]),
+                Some(Mesh::uv_sphere(1.0, 8, 4)),
+            )
+            .unwrap();
+        let moon = scene
+            .add(
+                Some(earth),
+                "moon",
+                Transformation::translation(matrix![2; 0; 0]),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(scene.find("moon"), Some(moon));
+        assert_eq!(scene.find("pluto"), None);
+        // the sun's rotation carries its children from +x round to -z
+        let origin = matrix![0; 0; 0];
+        assert_close(
+            scene.world_matrix(earth).unwrap().transform_point(origin),
+            matrix![0; 0; -10],
+            1e-5,
+        );
+        assert_close(
+            scene.world_matrix(moon).unwrap().transform_point(origin),
+            matrix![0; 0; -12],
+            1e-5,
+        );
+
+        let mut visited = vec![];
+        scene.traverse(|id, _, world| {
+            visited.push(id);
+            assert_close(*world, scene.world_matrix(id).unwrap(), 1e-6);
+        });
+        assert_eq!(visited, vec![sun, earth, moon]);
+
+        // a node can't become its own descendant
+        assert!(scene.reparent(sun, Some(moon)).is_err());
+        scene.reparent(moon, None).unwrap();
+        assert_eq!(scene.roots(), &[sun, moon]);
+        assert_close(
+            scene.world_matrix(moon).unwrap().transform_point(origin),
+            matrix![2; 0; 0],
+            1e-5,
+        );
+
+        scene.reparent(moon, Some(earth)).unwrap();
+        scene.remove(earth).unwrap();
+        assert!(scene.get(moon).is_none());
+        assert!(scene.get(sun).unwrap().children().is_empty());
+        assert!(scene
+            .add(Some(earth), "orphan", Transformation::default(), None)
+            .is_err());
+    }
+
+    #[test]
+    fn keyframe_interpolation() {
+        let keyframes = vec![(2.0, 4.0), (0.0, 0.0), (1.0, 1.0), (3.0, 9.0)];
+        let step = Track::new(Interpolation::Step, keyframes.clone());
+        let linear = Track::new(Interpolation::Linear, keyframes.clone());
+        let cubic = Track::new(Interpolation::Cubic, keyframes);
+
+        assert_eq!(step.sample(1.5), Some(1.0));
+        assert_eq!(linear.sample(1.5), Some(2.5));
+        assert_eq!(linear.sample(-1.0), Some(0.0));
+        assert_eq!(linear.sample(10.0), Some(9.0));
+        // the spline passes through the keyframes, and follows the curve more closely
+        assert_eq!(cubic.sample(2.0), Some(4.0));
+        let halfway = cubic.sample(1.5).unwrap();
+        assert!((halfway - 2.25).abs() < (2.5 - 2.25));
+        assert_eq!(
+            Track::<f32>::new(Interpolation::Linear, vec![]).sample(0.0),
+            None
+        );
+
+        // two keyframes at the same time jump from one value to the other
+        let jump = Track::new(
+            Interpolation::Cubic,
+            vec![(0.0, 0.0), (0.0, 1.0), (1.0, 2.0), (1.0, 5.0), (2.0, 5.0)],
+        );
+        for i in 0..=20 {
+            assert!(jump.sample(i as f32 * 0.1).unwrap().is_finite());
+        }
+        assert_eq!(jump.sample(0.0), Some(1.0));
+        assert_eq!(jump.sample(1.0), Some(5.0));
+    }
+
+    #[test]
+    fn animation_playback() {
+        let slide = Channel {
+            translation: Some(Track::new(
+                Interpolation::Linear,
+                vec![(0.0, matrix![0; 0; 0]), (2.0, matrix![4; 0; 0])],
+            )),
+            ..Default::default()
+        };
+        let clip = Clip::new().with_channel("box", slide);
+        assert_eq!(clip.duration(), 2.0);
+
+        let mut once = AnimationPlayer::new(clip.clone(), PlaybackMode::Once);
+        let mut looping = AnimationPlayer::new(clip.clone(), PlaybackMode::Loop);
+        let mut ping_pong = AnimationPlayer::new(clip.clone(), PlaybackMode::PingPong);
+        for player in [&mut once, &mut looping, &mut ping_pong] {
+            player.advance(2.5);
+        }
+        assert_eq!(once.time(), 2.0);
+        assert!(once.is_finished());
+        assert_eq!(looping.time(), 0.5);
+        assert_eq!(ping_pong.time(), 1.5);
+
+        let mut transformation = Transformation::scale(matrix![2; 2; 2]);
+        looping.apply("box", &mut transformation);
+        assert_close(transformation.translation, matrix![1; 0; 0], 1e-6);
+        // parts without a track are left alone
+        assert_close(transformation.scale, matrix![2; 2; 2], 1e-6);
+
+        let mut scene = SceneGraph::new();
+        let node = scene
+            .add(None, "box", Transformation::default(), None)
+            .unwrap();
+        let blend = Blend::new(once, looping, 0.25);
+        blend.apply_to(&mut scene);
+        // three quarters of the way from 4 to 1
+        assert_close(
+            scene.get(node).unwrap().transformation.translation,
+            matrix![3.25; 0; 0],
+            1e-5,
+        );
+    }
+
+    #[test]
+    fn linear_blend_skinning() {
+        // an arm along the x axis, with an elbow at x = 2
+        let mut skeleton = Skeleton::new();
+        let shoulder = skeleton
+            .add_joint("shoulder", None, Transformation::default())
+            .unwrap();
+        let elbow = skeleton
+            .add_joint(
+                "elbow",
+                Some(shoulder),
+                Transformation::translation(matrix![2; 0; 0]),
+            )
+            .unwrap();
+        assert!(skeleton
+            .add_joint("wrist", Some(5), Transformation::default())
+            .is_err());
+        skeleton.bind();
+
+        let upper =
+            Vertex::new(matrix![1; 0; 0]).with_joints([shoulder, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]);
+        let hand =
+            Vertex::new(matrix![3; 0; 0]).with_joints([elbow, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]);
+        let joint = Vertex::new(matrix![2; 0; 0])
+            .with_joints([shoulder, elbow, 0, 0], [0.5, 0.5, 0.0, 0.0]);
+        let mut arm = Mesh::new(vec![Triangle::from_vertices([upper, hand, joint])]);
+
+        // bending the elbow up by a right angle
+        let bend = Channel {
+            rotation: Some(Track::new(
+                Interpolation::Linear,
+                vec![(0.0, matrix![0; 0; 0]), (1.0, matrix![0; 0; PI / 2.0])],
+            )),
+            ..Default::default()
+        };
+        let clip = Clip::new().with_channel("elbow", bend);
+        clip.apply_to(1.0, &mut skeleton);
+
+        let matrices = skeleton.skinning_matrices();
+        let skin = |vertex| Skeleton::skin(&matrices, &vertex).position();
+        assert_close(skin(upper), matrix![1; 0; 0], 1e-5);
+        assert_close(skin(hand), matrix![2; 1; 0], 1e-5);
+        assert_close(skin(joint), matrix![2; 0; 0], 1e-5);
+
+        arm.set_skeleton(Some(skeleton));
+        let bounds = arm.bounding_box().unwrap();
+        assert_close(bounds.max, matrix![2; 1; 0], 1e-5);
+    }
+
+    #[test]
+    fn morph_targets() {
+        let normal = matrix![0; 0; 1];
+        let triangle = [matrix![0; 0; 0], matrix![1; 0; 0], matrix![0; 1; 0]]
+            .map(|x| Vertex::new(x).with_normal(Some(normal)));
+        let mut mesh = Mesh::new(vec![Triangle::from_vertices(triangle)]);
+        assert!(mesh
+            .add_morph_target(MorphTarget::new("broken", vec![], vec![]))
+            .is_err());
+        let zero = matrix![0; 0; 0];
+        let raise = MorphTarget::new("raise", vec![zero, zero, matrix![0; 0; 2]], vec![]);
+        let raise = mesh.add_morph_target(raise).unwrap();
+        let widen = MorphTarget::new("widen", vec![zero, matrix![3; 0; 0], zero], vec![]);
+        let widen = mesh.add_morph_target(widen).unwrap();
+
+        mesh.morph_weights_mut()[raise] = 0.5;
+        mesh.set_transformation(Transformation::translation(matrix![0; 0; 10]));
+        let bounds = mesh.bounding_box().unwrap();
+        assert_close(bounds.max, matrix![1; 1; 11], 1e-6);
+        assert_close(
+            mesh.local_bounding_box().unwrap().max,
+            matrix![1; 1; 1],
+            1e-6,
+        );
+
+        let grow = Channel {
+            weights: vec![
+                Track::new(Interpolation::Linear, vec![(0.0, 0.0)]),
+                Track::new(Interpolation::Linear, vec![(0.0, 0.0), (1.0, 1.0)]),
+            ],
+            ..Default::default()
+        };
+        let mut scene = SceneGraph::new();
+        let node = scene
+            .add(None, "face", Transformation::default(), Some(mesh))
+            .unwrap();
+        Clip::new()
+            .with_channel("face", grow)
+            .apply_to(0.5, &mut scene);
+        let mesh = scene.get(node).unwrap().mesh.as_ref().unwrap();
+        assert_eq!(mesh.morph_weights()[raise], 0.0);
+        assert_eq!(mesh.morph_weights()[widen], 0.5);
+        let posed = mesh.posed_triangles();
+        assert_close(posed[0][1].position(), matrix![2.5; 0; 0], 1e-6);
+        assert_close(posed[0][1].normal().unwrap(), normal, 1e-6);
+    }
+
+    #[test]
+    fn colour_quantisation() {
+        let red = Colour::new(1.0, 0.0, 0.0);
+        assert_eq!(ColourMode::Palette256.quantise(red), Color::Xterm(196));
+        assert_eq!(ColourMode::Palette16.quantise(red), Color::Xterm(9));
+        assert_eq!(ColourMode::Monochrome.quantise(red), Color::White);
+        // greys use the greyscale ramp when it's closer than the colour cube
+        assert_eq!(
+            ColourMode::Palette256.quantise(Colour::grey(0.5)),
+            Color::Xterm(244)
+        );
+        assert_eq!(
+            ColourMode::Palette256.quantise(Colour::from_rgb8(0, 135, 255)),
+            Color::Xterm(33)
+        );
+        assert_eq!(Colour::from_rgb8(12, 34, 56).to_rgb8(), [12, 34, 56]);
+    }
+
+    #[test]
+    fn triangle_rasterization() {
+        // a right triangle covering the lower left half of a 4x4 grid
+        let points = [matrix![0; 0; 1], matrix![0; 4; 1], matrix![4; 4; 3]];
+        let mut covered = vec![];
+        rasterize_triangle(points, 4, 4, |x, y, depth, weights| {
+            covered.push((x, y));
+            assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+            assert!((1.0..=3.0).contains(&depth));
+        });
+        assert_eq!(covered.len(), 10);
+        assert!(covered.iter().all(|&(x, y)| x <= y));
+
+        // perspective correction moves the midpoint of an edge towards the nearer end
+        let points = [matrix![0; 0; 1], matrix![0; 0; 1], matrix![2; 0; 1]];
+        rasterize_triangle(points, 4, 4, |_, _, _, _| panic!("degenerate triangle"));
+        let points = [matrix![0; 0; 1], matrix![8; 0; 3], matrix![0; 1; 1]];
+        rasterize_triangle(points, 8, 1, |x, _, depth, weights| {
+            if x == 3 {
+                assert!(weights[1] < 3.5 / 8.0);
+                assert!(depth < 2.0);
+            }
+        });
+    }
+
+    fn render_to_framebuffer(
+        mesh: &Mesh,
+        camera: &Camera,
+        renderer: &mut Renderer,
+        width: usize,
+        height: usize,
+    ) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(width, height);
+        renderer.colour_mode = ColourMode::Palette256;
+        mesh.draw(renderer, &mut framebuffer, camera);
+        framebuffer
+    }
+
+    #[test]
+    fn coloured_mesh_rendering() {
+        let mut cube = Mesh::cuboid(matrix![2; 2; 2]);
+        cube.set_material(Material::new(Colour::new(0.0, 1.0, 0.0)));
+        let camera = Camera::new(matrix![0; 0; 6], matrix![0; 0; 0]);
+        let framebuffer = render_to_framebuffer(&cube, &camera, &mut Renderer::new(), 40, 20);
+
+        let centre = framebuffer.get(20, 10);
+        assert_ne!(centre.glyph, ' ');
+        let code = centre.foreground.code();
+        // somewhere in the green column of the 6x6x6 cube, without any red or blue
+        assert!(
+            (16..232).contains(&code) && (code - 16).is_multiple_of(6) && (code - 16) / 36 == 0
+        );
+        assert_eq!(framebuffer.get(0, 0).glyph, ' ');
+
+        // the camera can sit inside a mesh, where all the triangles face away from it
+        let inside = Camera::new(matrix![0; 0; 0.5], matrix![0; 0; -1]);
+        let mut renderer = Renderer::new();
+        let framebuffer = render_to_framebuffer(&cube, &inside, &mut renderer, 40, 20);
+        assert!(framebuffer.cells().iter().all(|x| x.glyph == ' '));
+        renderer.cull_back_faces = false;
+        let framebuffer = render_to_framebuffer(&cube, &inside, &mut renderer, 40, 20);
+        assert!(framebuffer.cells().iter().all(|x| x.glyph != ' '));
+    }
+
+    #[test]
+    fn image_loading() {
+        let ascii = Image::parse(b"P3\n# a comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+        assert_eq!((ascii.width(), ascii.height()), (2, 1));
+        assert_eq!(ascii.get(0, 0), Colour::new(1.0, 0.0, 0.0));
+        assert_eq!(ascii.get(1, 0), Colour::new(0.0, 0.0, 1.0));
+
+        let mut binary = b"P6 2 1 255\n".to_vec();
+        binary.extend([255, 0, 0, 0, 0, 255]);
+        assert_eq!(Image::parse(&binary).unwrap(), ascii);
+
+        // 16 bit samples are big endian
+        let mut grey = b"P5 1 2 65535 ".to_vec();
+        grey.extend([0xff, 0xff, 0x00, 0x00]);
+        let grey = Image::parse(&grey).unwrap();
+        assert_eq!(grey.get(0, 0), Colour::WHITE);
+        assert_eq!(grey.get(0, 1), Colour::BLACK);
+        assert_eq!(
+            Image::parse(b"P2 1 1 4 2").unwrap().get(0, 0),
+            Colour::grey(0.5)
+        );
+
+        assert!(Image::parse(b"P6 2 1 255\n\xff\x00").is_err());
+        assert!(Image::parse(b"P4 1 1\n\x00").is_err());
+        assert!(Image::parse(b"P3 1 1 255 0 0").is_err());
+        // sizes from the header are checked before anything is allocated for them
+        let too_large = |bytes: &[u8]| matches!(Image::parse(bytes), Err(ImageError::Format(x)) if x == "image is too large");
+        assert!(too_large(b"P3 4294967296 4294967296 255\n"));
+        assert!(too_large(b"P6 18446744073709551615 2 255\n"));
+        assert!(matches!(
+            Image::parse(b"P2 100000 100000 255\n1 2 3"),
+            Err(ImageError::Format(_))
+        ));
+        assert!(Image::parse(b"P5 100000 100000 65535\n\x00\x01").is_err());
+    }
+
+    #[test]
+    fn texture_sampling() {
+        // black on the left, white on the right
+        let image = Image::from_pixels(2, 1, vec![Colour::BLACK, Colour::WHITE]).unwrap();
+        let mut texture = Texture::new(image);
+        texture.filter = Filter::Nearest;
+        assert_eq!(texture.sample(0.2, 0.5), Colour::BLACK);
+        assert_eq!(texture.sample(0.8, 0.5), Colour::WHITE);
+        assert_eq!(texture.sample(1.2, 0.5), Colour::BLACK);
+        texture.wrap = Wrap::Clamp;
+        assert_eq!(texture.sample(1.2, 0.5), Colour::WHITE);
+
+        texture.filter = Filter::Bilinear;
+        assert_eq!(texture.sample(0.5, 0.5), Colour::grey(0.5));
+        assert_eq!(texture.sample(0.0, 0.5), Colour::BLACK);
+        // repeating blends the edge with the opposite side
+        texture.wrap = Wrap::Repeat;
+        assert_eq!(texture.sample(0.0, 0.5), Colour::grey(0.5));
+
+        // v goes up the image, so the first row is at the top
+        let image = Image::from_pixels(1, 2, vec![Colour::WHITE, Colour::BLACK]).unwrap();
+        let mut texture = Texture::new(image);
+        texture.filter = Filter::Nearest;
+        assert_eq!(texture.sample(0.5, 0.9), Colour::WHITE);
+        assert!(Image::from_pixels(2, 2, vec![Colour::WHITE]).is_err());
+    }
+
+    #[test]
+    fn textured_mesh_rendering() {
+        // a square facing +z, red on the left half and blue on the right
+        let corner = |x: f32, y: f32| {
+            Vertex::new(matrix![x; y; 0]).with_uv(matrix![(x + 1.0) * 0.5; (y + 1.0) * 0.5])
+        };
+        let mut square = Mesh::new(vec![
+            Triangle::from_vertices([corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0)]),
+            Triangle::from_vertices([corner(-1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)]),
+        ]);
+        let mut texture = Texture::new(Image::parse(b"P3 2 1 1  1 0 0  0 0 1").unwrap());
+        texture.filter = Filter::Nearest;
+        texture.wrap = Wrap::Clamp;
+        square.set_material(Material::default().with_texture(Rc::new(texture)));
+        // turned so the left edge is nearer the camera than the right
+        square.set_transformation(Transformation::rotation(matrix![0; PI / 3.0; 0]));
+
+        let camera = Camera::new(matrix![0; 0; 3], matrix![0; 0; 0]);
+        let framebuffer = render_to_framebuffer(&square, &camera, &mut Renderer::new(), 60, 30);
+        let row: Vec<_> = (0..60)
+            .map(|x| framebuffer.get(x, 15))
+            .filter(|x| x.glyph != ' ')
+            .map(|x| x.foreground.code() - 16)
+            .collect();
+        let red = row.iter().take_while(|&&x| x % 36 == 0 && x != 0).count();
+        assert!(row[red..].iter().all(|&x| x < 6 && x != 0));
+
+        // the middle of the texture is at the origin, which is the middle of the screen,
+        // but interpolating linearly across the screen would put it further left
+        let left = (0..60)
+            .find(|&x| framebuffer.get(x, 15).glyph != ' ')
+            .unwrap();
+        let boundary = left + red;
+        assert!((29..=31).contains(&boundary));
+        assert!(boundary as f32 > left as f32 + row.len() as f32 * 0.5 + 1.0);
+    }
+
+    #[test]
+    fn material_library() {
+        let library = "
+            # a comment
+            newmtl red
+            Kd 1 0 0 # fully red
+            Ks 0.5
+            Ns 10
+            ramp  .oO#
+            newmtl glow
+            Ke 0 1 0
+            map_Kd -s 2 2 2 glow.ppm
+        ";
+        let mut requested = vec![];
+        let materials = parse_mtl(library, |name| {
+            requested.push(name.to_string());
+            Ok(Rc::new(Texture::new(Image::new(1, 1))))
+        })
+        .unwrap();
+        assert_eq!(requested, ["glow.ppm"]);
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].diffuse, Colour::new(1.0, 0.0, 0.0));
+        assert_eq!(materials[0].specular, Colour::grey(0.5));
+        assert_eq!(materials[0].shininess, 10.0);
+        // the darkest glyph is a space
+        assert_eq!(materials[0].ramp.as_deref(), Some(" .oO#"));
+        assert_eq!(glyph(" .oO#", 0.0), ' ');
+        assert_eq!(glyph("", 0.5), ' ');
+        assert_eq!(materials[1].emissive, Colour::new(0.0, 1.0, 0.0));
+        assert!(materials[1].texture.is_some());
+
+        // a square, then a triangle referring to its vertices from the end
+        let model = "
+            mtllib things.mtl
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0 # the corner checked below
+            v 0 1 0
+            vt 0 0
+            vt 1 1
+            vn 0 0 1
+            usemtl red
+            f 1/1/1 2/1/1 3/2/1 4//1
+            usemtl glow
+            f -4 -3 -2
+        ";
+        let load = |name: &str| {
+            assert_eq!(name, "things.mtl");
+            parse_mtl(library, |_| Ok(Rc::new(Texture::new(Image::new(1, 1)))))
+        };
+        let mesh = parse_obj(model, load).unwrap();
+        assert_eq!(mesh.triangles().len(), 3);
+        assert_eq!(mesh.materials().len(), 3);
+        assert_eq!(mesh.triangle_material(1).name, "red");
+        assert_eq!(mesh.triangle_material(2).name, "glow");
+        let corner = mesh.triangles()[0].vertices()[2];
+        assert_eq!(corner.position(), matrix![1; 1; 0]);
+        assert_eq!(corner.uv(), matrix![1; 1]);
+        assert_eq!(corner.normal(), Some(matrix![0; 0; 1]));
+        assert_eq!(
+            mesh.triangles()[2].vertices()[0].position(),
+            matrix![0; 0; 0]
+        );
+
+        assert!(matches!(
+            parse_obj("v 0 0 0\nf 1 2 3", |_| Ok(vec![])),
+            Err(ObjError::File(FileError::Parse { line: 2, .. }))
+        ));
+        assert!(matches!(
+            parse_obj("usemtl missing", |_| Ok(vec![])),
+            Err(ObjError::File(FileError::Parse { line: 1, .. }))
+        ));
+        assert!(parse_mtl("Kd 1 1 1", |_| unreachable!()).is_err());
+        assert!(matches!(
+            parse_mtl("newmtl blank\nramp ", |_| unreachable!()),
+            Err(ObjError::File(FileError::Parse { line: 2, .. }))
+        ));
+    }
+
+    #[test]
+    fn per_triangle_materials() {
+        let mut cube = Mesh::cuboid(matrix![2; 2; 2]);
+        let glow = cube.add_material(
+            Material::new(Colour::BLACK)
+                .with_emissive(Colour::new(0.0, 0.0, 1.0))
+                .with_ramp("o"),
+        );
+        assert!(cube.assign_material(0..13, glow).is_err());
+        assert!(cube.assign_material(0..12, 2).is_err());
+        #[allow(clippy::reversed_empty_ranges)]
+        let backwards = 5..2;
+        assert!(cube.assign_material(backwards, glow).is_err());
+        cube.assign_material(0..12, glow).unwrap();
+
+        // emissive surfaces are bright whatever the lighting
+        let camera = Camera::new(matrix![0; 0; 6], matrix![0; 0; 0]);
+        let mut renderer = Renderer::new();
+        renderer.lights = vec![Light::directional(matrix![0; 0; 1], Colour::WHITE, 1.0)];
+        let framebuffer = render_to_framebuffer(&cube, &camera, &mut renderer, 40, 20);
+        let centre = framebuffer.get(20, 10);
+        assert_eq!(centre.glyph, 'o');
+        assert_eq!(centre.foreground, Color::Xterm(21));
+
+        // with the default material, the side facing away from the light is dark
+        cube.assign_material(0..12, 0).unwrap();
+        let framebuffer = render_to_framebuffer(&cube, &camera, &mut renderer, 40, 20);
+        assert_eq!(framebuffer.get(20, 10).glyph, '.');
+    }
+
+    #[test]
+    fn light_types() {
+        let white = Colour::WHITE;
+        let attenuation = Attenuation::new(1.0, 0.5, 0.25);
+        assert_eq!(attenuation.at(2.0), 1.0 / 3.0);
+
+        let point =
+            Light::point(matrix![0; 4; 0], white, 2.0).with_attenuation(Attenuation::none());
+        let (direction, radiance) = point.incident(matrix![0; 0; 0]).unwrap();
+        assert_close(direction, matrix![0; 1; 0], 1e-6);
+        assert_eq!(radiance, Colour::grey(2.0));
+        assert!(Light::ambient(white, 1.0)
+            .incident(matrix![0; 0; 0])
+            .is_none());
+        // a point right on the light has no direction to it, so it's left unlit
+        assert!(point.incident(matrix![0; 4; 0]).is_none());
+        let on_light = illuminate(
+            &[point],
+            matrix![0; 4; 0],
+            matrix![0; 1; 0],
+            matrix![0; 1; 0],
+            8.0,
+        );
+        assert_eq!(on_light.diffuse, Colour::BLACK);
+        assert_eq!(on_light.specular, Colour::BLACK);
+        assert!(
+            std::panic::catch_unwind(|| Light::directional(matrix![0; 0; 0], white, 1.0)).is_err()
+        );
+        let aimless = || Light::spot(matrix![0; 4; 0], matrix![0; 0; 0], 0.2, 0.4, white, 1.0);
+        assert!(std::panic::catch_unwind(aimless).is_err());
+
+        // full strength inside the inner cone, nothing outside the outer cone
+        let spot = Light::spot(matrix![0; 4; 0], matrix![0; -1; 0], 0.2, 0.4, white, 1.0)
+            .with_attenuation(Attenuation::none());
+        let strength = |x: f32| spot.incident(matrix![x; 0; 0]).unwrap().1.r;
+        assert_eq!(strength(0.0), 1.0);
+        assert_eq!(strength(4.0 * 0.5f32.tan()), 0.0);
+        let edge = strength(4.0 * 0.3f32.tan());
+        assert!(0.0 < edge && edge < 1.0);
+
+        // moving a light moves where it shines from
+        let moved = point.transformed(&Transformation::translation(matrix![1; 0; 0]).to_matrix());
+        let (direction, _) = moved.incident(matrix![1; 0; 0]).unwrap();
+        assert_close(direction, matrix![0; 1; 0], 1e-6);
+
+        // the highlight peaks when the half vector lines up with the normal
+        let lights = [
+            Light::ambient(Colour::new(0.1, 0.0, 0.0), 1.0),
+            Light::directional(matrix![0; 0; -1], white, 1.0),
+        ];
+        let normal = matrix![0; 0; 1];
+        let head_on = illuminate(&lights, matrix![0; 0; 0], normal, normal, 8.0);
+        assert_eq!(head_on.diffuse, Colour::new(1.1, 1.0, 1.0));
+        assert_eq!(head_on.specular, white);
+        let glancing = matrix![1; 0; 0.1].normalized();
+        assert!(
+            illuminate(&lights, matrix![0; 0; 0], normal, glancing, 8.0)
+                .specular
+                .r
+                < 0.5
+        );
+        let behind = illuminate(&lights, matrix![0; 0; 0], -normal, normal, 8.0);
+        assert_eq!(behind.diffuse, Colour::new(0.1, 0.0, 0.0));
+    }
+
+    #[test]
+    fn scene_lighting() {
+        let mut scene = SceneGraph::new();
+        let cube = Mesh::cuboid(matrix![4; 4; 0.5]);
+        scene
+            .add(None, "wall", Transformation::default(), Some(cube))
+            .unwrap();
+        let light = Light::point(matrix![0; 0; 0], Colour::new(1.0, 0.0, 0.0), 3.0);
+        let light = scene
+            .add_light(
+                None,
+                "lamp",
+                Transformation::translation(matrix![-2; 0; 1]),
+                light,
+            )
+            .unwrap();
+        assert_eq!(scene.lights().len(), 1);
+
+        let camera = Camera::new(matrix![0; 0; 6], matrix![0; 0; 0]);
+        let mut renderer = Renderer::new();
+        renderer.colour_mode = ColourMode::Palette256;
+        let default_lights = renderer.lights.clone();
+        let mut render = |scene: &SceneGraph| {
+            let mut framebuffer = Framebuffer::new(40, 20);
+            scene.draw(&mut renderer, &mut framebuffer, &camera);
+            let brightness = |x: usize| GLYPH_RAMP.find(framebuffer.get(x, 10).glyph).unwrap();
+            (
+                brightness(12),
+                brightness(28),
+                framebuffer.get(12, 10).foreground,
+            )
+        };
+
+        let (left, right, colour) = render(&scene);
+        assert!(left > right);
+        // only red light reaches the wall
+        assert!(colour.code() > 16 && (colour.code() - 16) % 36 == 0);
+
+        scene.get_mut(light).unwrap().transformation =
+            Transformation::translation(matrix![2; 0; 1]);
+        let (left, right, _) = render(&scene);
+        assert!(left < right);
+        assert_eq!(renderer.lights, default_lights);
+    }
+
+    #[test]
+    fn shadow_mapping() {
+        // a cube floating above a ground plane, lit from above and to the left
+        let mut scene = SceneGraph::new();
+        let ground = Transformation::translation(matrix![0; -1; 0]);
+        scene
+            .add(
+                None,
+                "ground",
+                ground,
+                Some(Mesh::cuboid(matrix![10; 0.2; 10])),
+            )
+            .unwrap();
+        let cube = Transformation::translation(matrix![0; 0.5; 0]);
+        scene
+            .add(None, "cube", cube, Some(Mesh::cuboid(matrix![1; 1; 1])))
+            .unwrap();
+        let sun = Light::directional(matrix![1; -1; 0], Colour::WHITE, 0.8);
+        scene
+            .add_light(None, "sun", Transformation::default(), sun)
+            .unwrap();
+        scene
+            .add_light(
+                None,
+                "sky",
+                Transformation::default(),
+                Light::ambient(Colour::WHITE, 0.2),
+            )
+            .unwrap();
+
+        let camera = Camera::new(matrix![0; 8; 4], matrix![0; 0; 0]);
+        let (width, height) = (60, 30);
+        let render = |renderer: &mut Renderer| {
+            let mut framebuffer = Framebuffer::new(width, height);
+            scene.draw(renderer, &mut framebuffer, &camera);
+            // the brightness of the ground at a point
+            move |x: f32| {
+                let cell = camera.project(matrix![x; -0.9; 0], width, height).unwrap();
+                let cell = framebuffer.get(cell.x() as usize, cell.y() as usize);
+                GLYPH_RAMP.find(cell.glyph).unwrap()
+            }
+        };
+
+        let mut renderer = Renderer::new();
+        let unshadowed = render(&mut renderer);
+        renderer.shadows = Some(ShadowSettings::default());
+        let shadowed = render(&mut renderer);
+        // the shadow falls between x = 0.4 and 2.4, where only the ambient light reaches
+        assert!(shadowed(1.4) < unshadowed(1.4));
+        assert_eq!(shadowed(1.4), GLYPH_RAMP.find('-').unwrap());
+        assert_eq!(shadowed(-3.0), unshadowed(-3.0));
+        assert_eq!(shadowed(3.5), unshadowed(3.5));
+
+        // percentage-closer filtering gives partial shadow at the edge
+        let map = renderer.shadow_map(0).unwrap();
+        let mut settings = ShadowSettings::default();
+        assert_eq!(map.visibility(matrix![1.4; -0.9; 0], &settings), 0.0);
+        assert_eq!(map.visibility(matrix![-3; -0.9; 0], &settings), 1.0);
+        settings.softness = 4;
+        let edge = map.visibility(matrix![0.4; -0.9; 0], &settings);
+        assert!(0.2 < edge && edge < 0.8);
+        assert!(renderer.shadow_map(1).is_none());
+
+        // the demo's cube casts a shadow onto the ground to its left
+        let (width, height) = (80, 24);
+        let mut screen = HeadlessScreen::new(viewer(), width, height);
+        let framebuffer = screen.run(1, &[]).remove(0);
+        let camera = viewed(&screen).camera;
+        let ground = |x: f32| {
+            let cell = camera.project(matrix![x; -1.8; 0], width, height).unwrap();
+            let cell = framebuffer.get(cell.x() as usize, cell.y() as usize);
+            GLYPH_RAMP.find(cell.glyph).unwrap()
+        };
+        assert!(ground(-2.0) < ground(2.0));
+    }
+
+    #[test]
+    fn ray_intersection() {
+        let triangle = [matrix![0; 0; 0], matrix![2; 0; 0], matrix![0; 2; 0]];
+        let ray = Ray::new(matrix![0.5; 0.5; 3], matrix![0; 0; -2]);
+        let hit = ray.intersect_triangle(&triangle, 0.0).unwrap();
+        assert_eq!(hit.distance, 3.0);
+        assert_eq!(hit.weights, [0.5, 0.25, 0.25]);
+        assert_close(ray.at(hit.distance), matrix![0.5; 0.5; 0], 1e-6);
+        // from behind, past the edge, pointing away and too close
+        let behind = Ray::new(matrix![0.5; 0.5; -1], matrix![0; 0; 1]);
+        assert!(behind.intersect_triangle(&triangle, 0.0).is_some());
+        let outside = Ray::new(matrix![1.5; 1.5; 3], matrix![0; 0; -1]);
+        assert!(outside.intersect_triangle(&triangle, 0.0).is_none());
+        let away = Ray::new(matrix![0.5; 0.5; 3], matrix![0; 0; 1]);
+        assert!(away.intersect_triangle(&triangle, 0.0).is_none());
+        assert!(ray.intersect_triangle(&triangle, 3.5).is_none());
+
+        assert!(ray.hits_sphere(matrix![0; 0; 0], 1.0));
+        assert!(!ray.hits_sphere(matrix![3; 0; 0], 1.0));
+        assert!(!away.hits_sphere(matrix![0; 0; 0], 1.0));
+
+        // camera rays go back through the cells points project to
+        let camera = Camera::new(matrix![1; 2; 5], matrix![0; 0; 0]);
+        let point = matrix![0.3; -0.2; 0.4];
+        let cell = camera.project(point, 40, 20).unwrap();
+        let ray = camera.ray(cell.x(), cell.y(), 40, 20);
+        assert_close(
+            ray.direction,
+            (point - camera.position()).normalized(),
+            1e-5,
+        );
+    }
+
+    #[test]
+    fn ray_tracing() {
+        let thing = |size: Vec3, position: Vec3, material: Material| {
+            let mut mesh = Mesh::cuboid(size);
+            mesh.set_material(material);
+            mesh.set_transformation(Transformation::translation(position));
+            mesh
+        };
+        let red = Colour::new(1.0, 0.0, 0.0);
+        let glowing = Material::new(Colour::BLACK).with_emissive(red);
+        let mut tracer = RayTracer::new();
+        tracer.lights.clear();
+
+        // a mirror facing the camera reflects a glowing block behind it
+        let mut scene = TraceScene::new();
+        let mirror = Material::new(Colour::BLACK).with_reflectivity(1.0);
+        scene.add_mesh(
+            &thing(matrix![4; 4; 0.1], matrix![0; 0; 0], mirror),
+            &Matrix::identity(),
+        );
+        scene.add_mesh(
+            &thing(matrix![4; 4; 0.1], matrix![0; 0; 5], glowing.clone()),
+            &Matrix::identity(),
+        );
+        let ray = Ray::new(matrix![0; 0; 2], matrix![0; 0; -1]);
+        assert_eq!(tracer.trace(&scene, &ray, 0), red);
+        tracer.max_depth = 0;
+        assert_eq!(tracer.trace(&scene, &ray, 0), Colour::BLACK);
+        tracer.max_depth = 4;
+
+        // glass bends light, so a ray at 45 degrees comes out shifted towards the normal
+        let mut scene = TraceScene::new();
+        let glass = Material::default().with_transparency(1.0, 1.5);
+        scene.add_mesh(
+            &thing(matrix![4; 4; 1], matrix![0; 0; 0], glass),
+            &Matrix::identity(),
+        );
+        let target = thing(matrix![0.45; 2; 0.01], matrix![1.025; 0; -2], glowing);
+        scene.add_mesh(&target, &Matrix::identity());
+        let ray = Ray::new(matrix![-2.5; 0; 2], matrix![1; 0; -1]);
+        assert_eq!(tracer.trace(&scene, &ray, 0), red);
+        let mut straight = TraceScene::new();
+        straight.add_mesh(&target, &Matrix::identity());
+        assert_eq!(tracer.trace(&straight, &ray, 0), Colour::BLACK);
+
+        // a block hovering over the ground shadows the point below it
+        let mut scene = SceneGraph::new();
+        let ground = Transformation::translation(matrix![0; -1; 0]);
+        scene
+            .add(
+                None,
+                "ground",
+                ground,
+                Some(Mesh::cuboid(matrix![10; 0.2; 10])),
+            )
+            .unwrap();
+        scene
+            .add(
+                None,
+                "block",
+                Transformation::default(),
+                Some(Mesh::cuboid(matrix![1; 1; 1])),
+            )
+            .unwrap();
+        let sun = Light::directional(matrix![0; -1; 0], Colour::WHITE, 0.8);
+        scene
+            .add_light(None, "sun", Transformation::default(), sun)
+            .unwrap();
+        scene
+            .add_light(
+                None,
+                "sky",
+                Transformation::default(),
+                Light::ambient(Colour::WHITE, 0.2),
+            )
+            .unwrap();
+        let scene = TraceScene::from_scene(&scene);
+        let down = |tracer: &RayTracer, x: f32| {
+            let ray = Ray::new(matrix![x; -0.7; 0], matrix![0; -1; 0]);
+            tracer.trace(&scene, &ray, 0).r
+        };
+        assert_close(matrix![down(&tracer, 0.0)], matrix![0.2], 1e-5);
+        assert_close(matrix![down(&tracer, 2.0)], matrix![1.0], 1e-5);
+        tracer.shadows = false;
+        assert_close(matrix![down(&tracer, 0.0)], matrix![1.0], 1e-5);
+
+        // renders are saved as PPM images
+        let mut camera = Camera::new(matrix![0; 3; 6], matrix![0; 0; 0]);
+        camera.set_cell_aspect(1.0);
+        let image = tracer.render(&scene, &camera, 8, 6);
+        assert_ne!(image.get(4, 3), Colour::BLACK);
+        let saved = Image::parse(&image.to_ppm()).unwrap();
+        assert_eq!((saved.width(), saved.height()), (8, 6));
+        assert_eq!(saved.get(4, 3).to_rgb8(), image.get(4, 3).to_rgb8());
+    }
+
+    #[test]
+    fn path_tracing() {
+        // cosine weighting puts the average cosine at 2/3 rather than 1/2
+        let mut rng = StdRng::seed_from_u64(1);
+        let normal = matrix![0.6; 0; 0.8];
+        let cosines: Vec<f32> = (0..4000)
+            .map(|_| cosine_hemisphere(&mut rng, normal).dot(&normal))
+            .collect();
+        assert!(cosines.iter().all(|&x| x >= 0.0));
+        let mean = cosines.iter().sum::<f32>() / cosines.len() as f32;
+        assert!((mean - 2.0 / 3.0).abs() < 0.02);
+
+        // a block on a grey floor under a white sky, whose shading around the block is noisy
+        let mut scene = TraceScene::new();
+        let mut floor = Mesh::cuboid(matrix![40; 0.2; 40]);
+        floor.set_material(Material::new(Colour::grey(0.5)));
+        floor.set_transformation(Transformation::translation(matrix![0; -0.6; 0]));
+        scene.add_mesh(&floor, &Matrix::identity());
+        scene.add_mesh(&Mesh::cuboid(matrix![1; 1; 1]), &Matrix::identity());
+        let camera = Camera::new(matrix![0; 2; 3], matrix![0; 0; 0]);
+        let render = |seed: u64, samples: usize| {
+            let mut tracer = PathTracer::with_seed(seed);
+            tracer.background = Colour::WHITE;
+            tracer.samples_per_frame = samples;
+            tracer.render(&scene, &camera, 16, 8)
+        };
+        let reference = render(0, 128);
+        let error = |image: &Image| {
+            let pixels = image.pixels().iter().zip(reference.pixels());
+            pixels.map(|(a, b)| (a.r - b.r).abs()).sum::<f32>()
+        };
+        assert!(error(&render(1, 32)) < error(&render(1, 2)) * 0.5);
+
+        // samples accumulate until the camera moves
+        let mut tracer = PathTracer::with_seed(2);
+        tracer.render(&scene, &camera, 16, 8);
+        tracer.render(&scene, &camera, 16, 8);
+        assert_eq!(tracer.samples(), 2);
+        let mut moved = camera;
+        moved.set_position(matrix![0; 2; 4]);
+        tracer.render(&scene, &moved, 16, 8);
+        assert_eq!(tracer.samples(), 1);
+        tracer.render(&scene, &moved, 8, 8);
+        assert_eq!(tracer.samples(), 1);
+
+        // the demo's view gets another sample every frame while it's path traced,
+        // with the cube holding still so they all add up, until the key's pressed again
+        let mut screen = HeadlessScreen::new(viewer(), 20, 10);
+        screen.run(2, &[(0, Input::Pressed(Key::P))]);
+        let samples = |screen: &HeadlessScreen<SceneStack>| {
+            let path_tracer = viewed(screen).path_tracer.as_ref().unwrap();
+            let samples = path_tracer.borrow().samples();
+            samples
+        };
+        let (before, rotation) = (
+            samples(&screen),
+            viewed(&screen).mesh.transformation().rotation,
+        );
+        assert!(before > 0);
+        let framebuffer = screen.run(1, &[]).remove(0);
+        assert_eq!(samples(&screen), before + 1);
+        assert_eq!(viewed(&screen).mesh.transformation().rotation, rotation);
+        assert!((0..20).any(|x| framebuffer.get(x, 5).glyph != ' '));
+        screen.run(1, &[(0, Input::Pressed(Key::P))]);
+        assert!(viewed(&screen).path_tracer.is_none());
+
+        // glowing surfaces seen directly are exact, whatever else happens to the path
+        let mut scene = TraceScene::new();
+        let mut lamp = Mesh::cuboid(matrix![4; 4; 0.1]);
+        lamp.set_material(Material::default().with_emissive(Colour::new(0.0, 0.0, 2.0)));
+        scene.add_mesh(&lamp, &Matrix::identity());
+        let image = PathTracer::with_seed(3).render(
+            &scene,
+            &Camera::new(matrix![0; 0; 2], matrix![0; 0; 0]),
+            4,
+            4,
+        );
+        assert_eq!(image.get(2, 2), Colour::new(0.0, 0.0, 2.0));
+    }
+
+    #[test]
+    fn signed_distances() {
+        let point = matrix![3; 4; 0];
+        assert_eq!(Sdf::sphere(1.0).distance(point), 4.0);
+        assert_eq!(
+            Sdf::cuboid(matrix![2; 2; 2]).distance(matrix![3; 0; 0]),
+            2.0
+        );
+        assert_eq!(
+            Sdf::cuboid(matrix![2; 2; 2]).distance(matrix![0.5; 0; 0]),
+            -0.5
+        );
+        assert_eq!(Sdf::torus(2.0, 0.5).distance(matrix![2; 0; 0]), -0.5);
+        assert_eq!(Sdf::torus(2.0, 0.5).distance(matrix![0; 0; 0]), 1.5);
+        assert_eq!(Sdf::plane().distance(point), 4.0);
+
+        // shapes are moved and scaled by their transformation
+        let moved = Sdf::sphere(1.0).with_transformation(Transformation::translation(point));
+        assert_eq!(moved.distance(matrix![3; 4; 2]), 1.0);
+        let scaled = Sdf::sphere(1.0).with_transformation(Transformation::scale(matrix![2; 2; 2]));
+        assert_eq!(scaled.distance(matrix![5; 0; 0]), 3.0);
+
+        let a = Sdf::sphere(1.0);
+        let b =
+            Sdf::sphere(1.0).with_transformation(Transformation::translation(matrix![1.5; 0; 0]));
+        let at = |sdf: &Sdf, x: f32| sdf.distance(matrix![x; 0; 0]);
+        assert_eq!(at(&a.clone().union(b.clone()), 2.0), -0.5);
+        assert_eq!(at(&a.clone().intersection(b.clone()), 0.0), 0.5);
+        assert_eq!(at(&a.clone().subtraction(b.clone()), 0.75), 0.25);
+        // blending only ever adds material, most of all where the shapes meet
+        let smooth = a.clone().smooth_union(b.clone(), 0.5);
+        let union = a.clone().union(b.clone());
+        assert!(at(&smooth, 0.75) < at(&union, 0.75) - 0.05);
+        assert_eq!(at(&smooth, -3.0), at(&union, -3.0));
+
+        // combined shapes keep each part's material
+        let red = Material::new(Colour::new(1.0, 0.0, 0.0));
+        let both = a.with_material(red).union(b);
+        assert_eq!(
+            both.sample(matrix![-1; 0; 0]).1.diffuse,
+            Colour::new(1.0, 0.0, 0.0)
+        );
+        assert_eq!(both.sample(matrix![2.5; 0; 0]).1.diffuse, Colour::WHITE);
+
+        assert_close(
+            Sdf::sphere(1.0).normal(matrix![0; 0; 1]),
+            matrix![0; 0; 1],
+            1e-3,
+        );
+        let normal = Sdf::cuboid(matrix![2; 2; 2]).normal(matrix![0.2; 1; 0.3]);
+        assert_close(normal, matrix![0; 1; 0], 1e-3);
+    }
+
+    #[test]
+    fn raymarching() {
+        // a ball resting on the ground, lit from straight above
+        let ball =
+            Sdf::sphere(1.0).with_transformation(Transformation::translation(matrix![0; 1; 0]));
+        let scene = Sdf::plane().union(ball);
+        let mut marcher = Raymarcher::new();
+        marcher.lights = vec![
+            Light::ambient(Colour::WHITE, 0.2),
+            Light::directional(matrix![0; -1; 0], Colour::WHITE, 0.8),
+        ];
+        assert_close(
+            matrix![marcher
+                .march(&scene, matrix![0; 5; 0], matrix![0; -1; 0])
+                .unwrap()],
+            matrix![3],
+            1e-2,
+        );
+        assert!(marcher
+            .march(&scene, matrix![0; 5; 0], matrix![0; 1; 0])
+            .is_none());
+
+        // the ground is in shadow under the ball, with a soft edge around it
+        let up = matrix![0; 1; 0];
+        let shadow = |x: f32| marcher.soft_shadow(&scene, matrix![x; 0.01; 0], up, f32::INFINITY);
+        assert_eq!(shadow(0.5), 0.0);
+        assert_eq!(shadow(4.0), 1.0);
+        assert!(0.0 < shadow(1.1) && shadow(1.1) < 1.0);
+        // the crease where the ball meets the ground is darker
+        let occlusion = |x: f32| marcher.ambient_occlusion(&scene, matrix![x; 0; 0], up);
+        assert!(occlusion(0.3) < occlusion(4.0));
+        assert_eq!(occlusion(4.0), 1.0);
+
+        // shapes share the depth buffer with meshes, so a cube in front hides the ball
+        let camera = Camera::new(matrix![0; 1; 6], matrix![0; 1; 0]);
+        let mut renderer = Renderer::new();
+        let mut cube = Mesh::cuboid(matrix![0.5; 0.5; 0.5]);
+        cube.set_material(Material::new(Colour::new(1.0, 0.0, 0.0)));
+        cube.set_transformation(Transformation::translation(matrix![0; 1; 3]));
+        let mut framebuffer = render_to_framebuffer(&cube, &camera, &mut renderer, 40, 20);
+        marcher.draw(&renderer, &mut framebuffer, &camera, &scene);
+        let cell = |x: usize, y: usize| *framebuffer.get(x, y);
+        assert_eq!(
+            cell(20, 10).foreground,
+            Color::Xterm(
+                ColourMode::Palette256
+                    .quantise(Colour::new(0.8, 0.0, 0.0))
+                    .code()
+            )
+        );
+        // the edge of the ball shows beside the cube, and is brightest on top
+        let ball = |y: usize| GLYPH_RAMP.find(cell(15, y).glyph).unwrap();
+        assert!(ball(8) > ball(10));
+        // with the ground lit right up to the horizon
+        assert_eq!(cell(0, 11).glyph, '@');
+        assert_eq!(cell(0, 10).glyph, ' ');
+    }
+
+    #[test]
+    fn framebuffer_output() {
+        let mut framebuffer = Framebuffer::new(4, 2);
+        assert!(framebuffer.test_depth(1, 0, 2.0));
+        assert!(!framebuffer.test_depth(1, 0, 3.0));
+        assert!(framebuffer.test_depth(1, 0, 1.0));
+        assert_eq!(framebuffer.get(1, 0).depth, 1.0);
+        framebuffer.draw_text(2, 1, "abc", Color::Xterm(9));
+        framebuffer.get_mut(0, 0).glyph = '#';
+        framebuffer.get_mut(0, 0).background = Color::Xterm(4);
+
+        // text is cut off at the edge
+        assert_eq!(framebuffer.to_text(), "#   \n  ab\n");
+        assert_eq!(
+            framebuffer.to_ansi(),
+            "\x1b[38;5;231m\x1b[48;5;4m#\x1b[38;5;231m\x1b[48;5;16m   \x1b[0m\n\
+             \x1b[38;5;231m\x1b[48;5;16m  \x1b[38;5;9m\x1b[48;5;16mab\x1b[0m\n"
+        );
+
+        let mut canvas = Canvas::new(ruscii::spatial::Vec2::xy(4, 2), &VisualElement::default());
+        framebuffer.blit(&mut Pencil::new(&mut canvas));
+        let cell = canvas.elem(ruscii::spatial::Vec2::xy(3, 1)).unwrap();
+        assert_eq!((cell.value, cell.foreground), ('b', Color::Xterm(9)));
+        let cell = canvas.elem(ruscii::spatial::Vec2::xy(0, 0)).unwrap();
+        assert_eq!((cell.value, cell.background), ('#', Color::Xterm(4)));
+
+        framebuffer.clear();
+        assert!(framebuffer.cells().iter().all(|x| *x == Default::default()));
+        framebuffer.resize(3, 1);
+        assert_eq!(framebuffer.to_text(), "   \n");
+
+        // in 24 bit colour the exact colour is written out, while ruscii still gets the palette
+        let mut renderer = Renderer::new();
+        renderer.colour_mode = ColourMode::TrueColour;
+        let orange = Colour::from_rgb8(255, 128, 0);
+        renderer.plot(&mut framebuffer, 0, 0, 1.0, orange, None);
+        assert!(framebuffer
+            .to_ansi()
+            .starts_with("\x1b[38;2;255;128;0m\x1b[48;5;16m@"));
+        assert_eq!(
+            framebuffer.get(0, 0).foreground,
+            ColourMode::Palette256.quantise(orange)
+        );
+        renderer.colour_mode = ColourMode::Palette256;
+        renderer.plot(&mut framebuffer, 0, 0, 1.0, orange, None);
+        assert!(framebuffer.to_ansi().starts_with("\x1b[38;5;208m"));
+    }
+
+    /// The demo's viewer on its own, without the menu
+    fn viewer() -> SceneStack {
+        SceneStack::new(Viewer::new(Bindings::new()))
+    }
+
+    fn viewed(screen: &HeadlessScreen<SceneStack>) -> &Viewer {
+        screen.state().top_as().unwrap()
+    }
+
+    /// Remembers every callback, and draws how many updates it's had
+    #[derive(Default)]
+    struct RecordingState {
+        events: Vec<String>,
+        updates: usize,
+        /// Time simulated in fixed steps, and the interpolation alpha after each update
+        simulated: f32,
+        alphas: Vec<f32>,
+    }
+
+    impl GameState for RecordingState {
+        fn update(&mut self, _dt: f32, context: &mut Context) {
+            self.updates += 1;
+            self.events.push(format!("update {}", context.step()));
+            self.alphas.push(context.alpha());
+        }
+
+        fn fixed_update(&mut self, step: f32, _context: &mut Context) {
+            self.simulated += step;
+        }
+
+        fn draw(&self, framebuffer: &mut Framebuffer) {
+            framebuffer.draw_text(0, 0, &self.updates.to_string(), Color::White);
+        }
+
+        fn key_pressed(&mut self, key: Key, _context: &mut Context) {
+            self.events.push(format!("pressed {key:?}"));
+        }
+
+        fn key_released(&mut self, key: Key, context: &mut Context) {
+            self.events.push(format!("released {key:?}"));
+            if key == Key::Q {
+                context.stop();
+            }
+        }
+
+        fn key_down(&mut self, key: Key, _context: &mut Context) {
+            self.events.push(format!("down {key:?}"));
+        }
+
+        fn resize(&mut self, width: usize, height: usize, _context: &mut Context) {
+            self.events.push(format!("resize {width}x{height}"));
+        }
+
+        fn mouse_dragged(
+            &mut self,
+            button: MouseButton,
+            position: (usize, usize),
+            modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+            let shift = if modifiers.shift { " with shift" } else { "" };
+            self.events
+                .push(format!("dragged {button:?} to {position:?}{shift}"));
+        }
+
+        fn mouse_scrolled(
+            &mut self,
+            amount: i32,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+            self.events.push(format!("scrolled {amount}"));
+        }
+    }
+
+    #[test]
+    fn headless_running() {
+        let mut screen = HeadlessScreen::new(RecordingState::default(), 3, 1);
+        let script = [
+            (0, Input::Pressed(Key::A)),
+            (1, Input::Down(Key::B)),
+            (2, Input::Released(Key::A)),
+            (3, Input::Released(Key::Q)),
+        ];
+        let frames = screen.run(10, &script);
+        // the state stops itself on the fourth frame
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[2].to_text(), "3  \n");
+        assert_eq!(
+            screen.state().events,
+            [
+                "resize 3x1",
+                "pressed A",
+                "down A",
+                "update 0",
+                "down A",
+                "down B",
+                "update 1",
+                "released A",
+                "update 2",
+                "released Q",
+                "update 3",
+            ]
+        );
+        assert!(!screen.context().is_running());
+        assert!(screen.run(1, &[]).is_empty());
+
+        // the demo spins its cube until escape is released
+        let mut screen = HeadlessScreen::new(viewer(), 40, 20);
+        let frames = screen.run(5, &[(2, Input::Released(Key::Esc))]);
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|x| x.get(20, 10).glyph != ' '));
+        assert_ne!(frames[0].to_text(), frames[1].to_text());
+    }
+
+    #[test]
+    fn window_resizing() {
+        let mut screen = HeadlessScreen::new(RecordingState::default(), 4, 2);
+        screen.run(2, &[]);
+        screen.resize(6, 3);
+        let frames = screen.run(2, &[]);
+        assert_eq!(screen.context().size(), (6, 3));
+        assert!(frames.iter().all(|x| (x.width(), x.height()) == (6, 3)));
+        let resizes: Vec<_> = screen
+            .state()
+            .events
+            .iter()
+            .filter(|x| x.starts_with("resize"))
+            .collect();
+        assert_eq!(resizes, ["resize 4x2", "resize 6x3"]);
+
+        // the demo's view follows the window's shape without moving the camera,
+        // so resizing keeps wherever it's been turned to
+        let mut screen = HeadlessScreen::new(viewer(), 80, 40);
+        let left = MouseButton::Left;
+        let framed = viewed(&screen).camera;
+        screen.run(
+            2,
+            &[
+                (
+                    0,
+                    Input::Mouse(MouseEvent::new(MouseEventKind::Down(left), 40, 20)),
+                ),
+                (
+                    1,
+                    Input::Mouse(MouseEvent::new(MouseEventKind::Dragged(left), 50, 20)),
+                ),
+            ],
+        );
+        let turned = viewed(&screen).camera;
+        assert_ne!(turned.position(), framed.position());
+        for (width, height) in [(79, 40), (16, 40), (120, 30)] {
+            screen.resize(width, height);
+            let frame = screen.run(1, &[]).remove(0);
+            assert_eq!((frame.width(), frame.height()), (width, height));
+            assert_eq!(viewed(&screen).camera, turned);
+        }
+    }
+
+    /// Every scene with a golden file, drawn with each way of rendering
+    fn snapshot_scenes() -> Vec<(&'static str, Framebuffer)> {
+        let (width, height) = (40, 20);
+        let mut renderer = Renderer::new();
+        let mut scenes = Vec::new();
+        let mut rasterized = |name, mesh: &Mesh, camera: &Camera| {
+            let framebuffer = render_to_framebuffer(mesh, camera, &mut renderer, width, height);
+            scenes.push((name, framebuffer));
+        };
+
+        let front = Camera::new(matrix![0; 0; 5], matrix![0; 0; 0]);
+        let mut cube = Mesh::cuboid(matrix![2; 2; 2]);
+        cube.set_transformation(Transformation::rotation(matrix![0.5; 0.7; 0]));
+        rasterized("cube", &cube, &front);
+        let sphere = Mesh::uv_sphere(1.5, 16, 8);
+        rasterized("sphere", &sphere, &front);
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/models/pyramid.obj");
+        let mut pyramid = load_obj(path).unwrap();
+        pyramid.set_transformation(Transformation::rotation(matrix![0; 0.4; 0]));
+        let above = Camera::new(matrix![0; 2; 4], matrix![0; 0.5; 0]);
+        rasterized("pyramid_model", &pyramid, &above);
+
+        // a cube casting a shadow onto the ground
+        let mut scene = SceneGraph::new();
+        let ground = Mesh::cuboid(matrix![10; 0.2; 10]);
+        let ground_position = Transformation::translation(matrix![0; -1; 0]);
+        scene
+            .add(None, "ground", ground_position, Some(ground))
+            .unwrap();
+        let floating = Transformation::translation(matrix![0; 0.5; 0]);
+        let small_cube = Mesh::cuboid(matrix![1; 1; 1]);
+        scene.add(None, "cube", floating, Some(small_cube)).unwrap();
+        let camera = Camera::new(matrix![0; 6; 5], matrix![0; 0; 0]);
+        let mut renderer = Renderer::new();
+        renderer.shadows = Some(ShadowSettings::default());
+        let mut framebuffer = Framebuffer::new(width, height);
+        scene.draw(&mut renderer, &mut framebuffer, &camera);
+        scenes.push(("shadows", framebuffer));
+
+        // the same scene through both tracers
+        let trace_scene = TraceScene::from_scene(&scene);
+        let ray_tracer = RayTracer::new();
+        let mut framebuffer = Framebuffer::new(width, height);
+        let image = ray_tracer.render(&trace_scene, &camera, width, height);
+        renderer.draw_image(&mut framebuffer, &image);
+        scenes.push(("raytraced", framebuffer));
+        let mut trace_scene = trace_scene;
+        trace_scene.lights = ray_tracer.lights.clone();
+        let mut path_tracer = PathTracer::with_seed(1);
+        path_tracer.samples_per_frame = 8;
+        let mut framebuffer = Framebuffer::new(width, height);
+        let image = path_tracer.render(&trace_scene, &camera, width, height);
+        renderer.draw_image(&mut framebuffer, &image);
+        scenes.push(("pathtraced", framebuffer));
+
+        let ring = Sdf::torus(1.0, 0.3)
+            .with_transformation(Transformation::new(
+                matrix![0; 0.6; 0],
+                matrix![1; 0; 0],
+                matrix![1; 1; 1],
+            ))
+            .smooth_union(Sdf::sphere(0.5), 0.3);
+        let sdf = Sdf::plane().union(ring.subtraction(Sdf::cuboid(matrix![3; 3; 0.4])));
+        let mut framebuffer = Framebuffer::new(width, height);
+        Raymarcher::new().draw(&renderer, &mut framebuffer, &above, &sdf);
+        scenes.push(("raymarched", framebuffer));
+        scenes
+    }
+
+    #[test]
+    fn snapshots() {
+        let failures: Vec<String> = snapshot_scenes()
+            .iter()
+            .filter_map(|(name, framebuffer)| match *name {
+                // sampling noise makes this one sensitive to rounding, so allow a glyph either way
+                "pathtraced" => check_within(name, framebuffer, GLYPH_RAMP, 1).err(),
+                _ => check(name, framebuffer).err(),
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+
+        assert_eq!(diff("ab\ncd\n", "ab\ncd\n"), None);
+        let difference = diff("ab\ncd\n", "ab\nxd\ne\n").unwrap();
+        assert!(difference.starts_with("expected 2x2 cells, got 2x3\n"));
+        assert!(difference.contains("   1 expected |cd|\n   1   actual |xd|\n              |^\n"));
+        assert!(difference.contains("  (0, 1): expected 'c', got 'x'\n"));
+        assert!(difference.contains("  (0, 2): expected nothing, got 'e'\n"));
+        assert_eq!(diff_within(".:\n", ":.\n", ".:-", 1), None);
+        assert!(diff_within(".:\n", "-.\n", ".:-", 1)
+            .unwrap()
+            .contains("1 different cell:"));
+    }
+
+    #[test]
+    fn frame_timing() {
+        let mut timestep = FixedTimestep::new(0.1);
+        assert_eq!(timestep.advance(0.25), 2);
+        assert_close(matrix![timestep.alpha()], matrix![0.5], 1e-4);
+        assert_eq!(timestep.advance(0.06), 1);
+        assert_close(matrix![timestep.alpha()], matrix![0.1], 1e-4);
+        // a long stall only catches up so far
+        assert_eq!(timestep.advance(5.0), timestep.max_steps);
+        assert_eq!(timestep.alpha(), 0.0);
+        // steps which would never move time on are refused
+        for step in [0.0, -0.1, f32::NAN] {
+            assert!(std::panic::catch_unwind(|| FixedTimestep::new(step)).is_err());
+        }
+
+        let mut screen =
+            HeadlessScreen::new(RecordingState::default(), 1, 1).with_fixed_timestep(0.0625);
+        screen.frame_time = 0.09375;
+        screen.run(4, &[]);
+        assert_eq!(screen.context().frame_time(), 0.09375);
+        // six steps in 0.375 seconds, with the time left over each frame shown by alpha
+        assert_eq!(screen.state().simulated, 0.375);
+        assert_eq!(screen.state().alphas, [0.5, 0.0, 0.5, 0.0]);
+
+        // the demo spins at the same speed whatever the frame rate
+        let spin = |frames: usize, frame_time: f32| {
+            let mut screen = HeadlessScreen::new(viewer(), 20, 10);
+            screen.frame_time = frame_time;
+            screen.run(frames, &[]);
+            viewed(&screen).mesh.transformation().rotation
+        };
+        assert_close(spin(10, 0.1), spin(40, 0.025), 1e-4);
+        // but a stall doesn't make it jump
+        assert_close(spin(1, 10.0), spin(1, MAX_FRAME_TIME), 1e-6);
+    }
+
+    #[test]
+    fn orbit_controller() {
+        let camera = Camera::new(matrix![3; 4; 0], matrix![0; 0; 0]);
+        let mut orbit = OrbitController::from_camera(&camera);
+        assert_close(orbit.position(), matrix![3; 4; 0], 1e-5);
+        assert_eq!(orbit.distance, 5.0);
+
+        // dragging right turns the camera left around the target, and down raises it
+        let left = MouseButton::Left;
+        let (yaw, pitch) = (orbit.yaw, orbit.pitch);
+        orbit.mouse_down(left, (10, 10));
+        orbit.mouse_dragged(left, (14, 10), Modifiers::default());
+        orbit.mouse_dragged(left, (14, 11), Modifiers::default());
+        assert_close(
+            matrix![orbit.yaw],
+            matrix![yaw - 4.0 * orbit.rotate_speed],
+            1e-5,
+        );
+        assert_close(
+            matrix![orbit.pitch],
+            matrix![pitch + 2.0 * orbit.rotate_speed],
+            1e-5,
+        );
+        orbit.mouse_dragged(left, (14, 200), Modifiers::default());
+        assert!(orbit.pitch < PI / 2.0);
+        assert_close(
+            matrix![(orbit.position() - orbit.target).length()],
+            matrix![5],
+            1e-4,
+        );
+
+        // a new drag starts from where the button went down, not where the last one ended
+        orbit.mouse_up(left, (14, 200));
+        let yaw = orbit.yaw;
+        orbit.mouse_down(left, (0, 0));
+        orbit.mouse_dragged(left, (1, 0), Modifiers::default());
+        assert_close(matrix![orbit.yaw], matrix![yaw - orbit.rotate_speed], 1e-5);
+
+        orbit.mouse_scrolled(2);
+        assert_close(matrix![orbit.distance], matrix![5.0 * 0.81], 1e-4);
+        orbit.mouse_scrolled(-100);
+        assert_eq!(orbit.distance, orbit.max_distance);
+
+        // panning moves the target across the view without turning
+        let mut orbit = OrbitController::from_camera(&camera);
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        orbit.mouse_down(left, (0, 0));
+        orbit.mouse_dragged(left, (3, 0), shift);
+        let mut camera = camera;
+        orbit.apply(&mut camera);
+        assert_close(
+            camera.forward(),
+            (matrix![0; 0; 0] - matrix![3; 4; 0]).normalized(),
+            1e-5,
+        );
+        assert!(camera.target().dot(&camera.right()) < 0.0);
+        assert_close(
+            matrix![camera.target().dot(&camera.forward())],
+            matrix![0],
+            1e-5,
+        );
+    }
+
+    #[test]
+    fn mouse_input() {
+        let mut screen = HeadlessScreen::new(RecordingState::default(), 4, 2);
+        let drag = MouseEvent::new(MouseEventKind::Dragged(MouseButton::Middle), 2, 1);
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        screen.run(
+            2,
+            &[
+                (0, Input::Mouse(drag.with_modifiers(shift))),
+                (
+                    1,
+                    Input::Mouse(MouseEvent::new(MouseEventKind::Scrolled(-1), 0, 0)),
+                ),
+                (1, Input::Pressed(Key::A)),
+            ],
+        );
+        let events: Vec<_> = screen
+            .state()
+            .events
+            .iter()
+            .filter(|x| !x.starts_with("update"))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "resize 4x2",
+                "dragged Middle to (2, 1) with shift",
+                "pressed A",
+                "down A",
+                "scrolled -1",
+            ]
+        );
+
+        // dragging the demo's view turns the camera, and scrolling moves it closer
+        let mut screen = HeadlessScreen::new(viewer(), 40, 20);
+        screen.run(1, &[]);
+        let before = viewed(&screen).camera;
+        let left = MouseButton::Left;
+        screen.run(
+            3,
+            &[
+                (
+                    0,
+                    Input::Mouse(MouseEvent::new(MouseEventKind::Down(left), 20, 10)),
+                ),
+                (
+                    1,
+                    Input::Mouse(MouseEvent::new(MouseEventKind::Dragged(left), 30, 10)),
+                ),
+                (
+                    2,
+                    Input::Mouse(MouseEvent::new(MouseEventKind::Scrolled(3), 30, 10)),
+                ),
+            ],
+        );
+        let after = viewed(&screen).camera;
+        assert_eq!(after.target(), before.target());
+        assert!(after.position().x() < before.position().x() - 1.0);
+        let distance = |x: &Camera| (x.position() - x.target()).length();
+        assert!(distance(&after) < distance(&before) * 0.8);
+    }
+
+    #[test]
+    fn camera_controllers() {
+        let camera = Camera::new(matrix![0; 0; 5], matrix![0; 0; 0]);
+
+        // held keys turn and zoom the orbit for as long as they're held
+        let mut orbit = OrbitController::from_camera(&camera);
+        orbit.action_down(Action::TurnRight);
+        orbit.action_down(Action::ZoomIn);
+        orbit.update(0.5);
+        assert_close(matrix![orbit.yaw], matrix![0.75], 1e-5);
+        assert_close(matrix![orbit.distance], matrix![5.0 * 0.9f32.powi(5)], 1e-4);
+        orbit.update(0.5);
+        assert_close(matrix![orbit.yaw], matrix![0.75], 1e-5);
+
+        // smoothed snapping glides there the short way round
+        let mut orbit = OrbitController::from_camera(&camera).with_smoothing(0.25);
+        orbit.yaw = 3.0 * TAU + 0.1;
+        orbit.reset(&camera);
+        orbit.snap(View::Side);
+        assert_close(matrix![orbit.yaw], matrix![PI / 2.0], 1e-5);
+        let mut moved = camera;
+        orbit.update(0.1);
+        orbit.apply(&mut moved);
+        assert!(moved.position().x() > 0.5 && moved.position().z() > 0.5);
+        for _ in 0..100 {
+            orbit.update(0.1);
+        }
+        orbit.apply(&mut moved);
+        assert_close(moved.position(), matrix![5; 0; 0], 1e-3);
+
+        let mut fly = FreeFlyController::from_camera(&camera);
+        let mut moved = camera;
+        fly.apply(&mut moved);
+        assert_close(moved.forward(), camera.forward(), 1e-5);
+        fly.action_down(Action::MoveForward);
+        fly.action_down(Action::MoveUp);
+        fly.update(1.0);
+        assert_close(
+            fly.position,
+            matrix![0; 2.0f32.sqrt(); 5.0 - 2.0f32.sqrt()],
+            1e-4,
+        );
+        fly.action_pressed(Action::SpeedUp);
+        assert_eq!(fly.speed, 3.0);
+        fly.action_down(Action::TurnLeft);
+        fly.update(PI / 3.0);
+        fly.apply(&mut moved);
+        assert_close(moved.forward(), matrix![-1; 0; 0], 1e-4);
+
+        // with inertia the camera takes a while to get going and to stop
+        let mut fly = FreeFlyController::from_camera(&camera).with_smoothing(0.5);
+        fly.action_down(Action::MoveRight);
+        fly.update(0.1);
+        let speed = fly.velocity().length();
+        assert!(speed > 0.0 && speed < fly.speed / 2.0);
+        fly.update(0.1);
+        assert!(fly.velocity().x() > 0.0 && fly.velocity().length() < speed);
+
+        let mut turntable = TurntableController::from_camera(&camera).with_speed(0.5);
+        turntable.update(1.0);
+        assert_close(matrix![turntable.angle()], matrix![0.5], 1e-5);
+        turntable.action_pressed(Action::Pause);
+        turntable.update(1.0);
+        assert_close(matrix![turntable.angle()], matrix![0.5], 1e-5);
+        turntable.action_pressed(Action::Pause);
+        turntable.action_pressed(Action::TopView);
+        assert!(turntable.paused);
+        turntable.update(1.0);
+        let mut moved = camera;
+        turntable.apply(&mut moved);
+        assert!(moved.position().y() > 4.99);
+        assert_eq!(moved.target(), camera.target());
+
+        // tab hands the demo's camera over to the next controller, which is free-fly
+        let mut screen = HeadlessScreen::new(viewer(), 40, 20);
+        screen.run(1, &[]);
+        let before = viewed(&screen).camera;
+        screen.run(
+            2,
+            &[(0, Input::Pressed(Key::Tab)), (1, Input::Pressed(Key::W))],
+        );
+        let after = viewed(&screen).camera;
+        assert_close(after.forward(), before.forward(), 1e-4);
+        assert!((after.position() - before.position()).dot(&before.forward()) > 0.0);
+    }
+
+    #[test]
+    fn key_bindings() {
+        let bindings = Bindings::new();
+        assert!(bindings.matches(Action::Quit, Key::Esc));
+        assert_eq!(
+            bindings.actions(Key::Equal),
+            [Action::ZoomIn, Action::SpeedUp]
+        );
+        assert_eq!(key_from_name("PageUp"), Some(Key::PageUp));
+        assert_eq!(key_from_name("7"), Some(Key::Num7));
+        assert_eq!(key_from_name("["), Some(Key::LeftBracket));
+        assert_eq!(key_from_name("pgup"), None);
+        assert_eq!(key_from_name("unknown"), None);
+
+        let bindings = Bindings::parse(
+            "# for a keyboard without an escape key\n\
+             quit = q `\n\
+             \n\
+             move_forward = Up W # arrow or letter\n\
+             pause =\n\
+             zoom_in = = ]\n",
+        )
+        .unwrap();
+        assert_eq!(bindings.keys(Action::Quit), [Key::Q, Key::Grave]);
+        assert_eq!(bindings.keys(Action::MoveForward), [Key::Up, Key::W]);
+        assert_eq!(
+            bindings.keys(Action::ZoomIn),
+            [Key::Equal, Key::RightBracket]
+        );
+        assert!(bindings.keys(Action::Pause).is_empty());
+        assert_eq!(bindings.keys(Action::MoveBack), [Key::S]);
+        assert_eq!(Bindings::parse(&bindings.to_config()).unwrap(), bindings);
+
+        // a bad line leaves the bindings as they were
+        let mut overridden = bindings.clone();
+        for (source, line) in [
+            ("quit = q\njump = space", 2),
+            ("quit = escape", 1),
+            ("\n\nquit", 3),
+            ("quit = unknown", 1),
+        ] {
+            let error = overridden.override_with(source);
+            assert!(matches!(error, Err(FileError::Parse { line: x, .. }) if x == line));
+        }
+        assert_eq!(overridden, bindings);
+
+        // the demo quits with whichever key is bound
+        let stack = SceneStack::new(Viewer::new(bindings));
+        let mut screen = HeadlessScreen::new(stack, 4, 2);
+        screen.run(
+            5,
+            &[
+                (1, Input::Released(Key::Esc)),
+                (2, Input::Released(Key::Grave)),
+            ],
+        );
+        assert!(!screen.context().is_running());
+        assert_eq!(screen.context().step(), 3);
+    }
+
+    /// Fills the screen with a random letter every frame, and remembers its input
+    #[derive(Default)]
+    struct NoiseState {
+        glyph: char,
+        events: Vec<String>,
+    }
+
+    impl GameState for NoiseState {
+        fn update(&mut self, dt: f32, context: &mut Context) {
+            self.glyph = context.rng().gen_range('a'..='z');
+            self.events.push(format!("update {dt}"));
+        }
+
+        fn draw(&self, framebuffer: &mut Framebuffer) {
+            for y in 0..framebuffer.height() {
+                let row: String = vec![self.glyph; framebuffer.width()].into_iter().collect();
+                framebuffer.draw_text(0, y, &row, Color::White);
+            }
+        }
+
+        fn key_pressed(&mut self, key: Key, _context: &mut Context) {
+            self.events.push(format!("pressed {key:?}"));
+        }
+
+        fn key_released(&mut self, key: Key, _context: &mut Context) {
+            self.events.push(format!("released {key:?}"));
+        }
+
+        fn key_down(&mut self, key: Key, _context: &mut Context) {
+            self.events.push(format!("down {key:?}"));
+        }
+
+        fn mouse_dragged(
+            &mut self,
+            button: MouseButton,
+            position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+            self.events
+                .push(format!("dragged {button:?} to {position:?}"));
+        }
+    }
+
+    #[test]
+    fn recording_and_replay() {
+        let frame = |time, size, inputs: &[Input]| RecordedFrame {
+            time,
+            size,
+            inputs: inputs.to_vec(),
+        };
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let drag = MouseEvent::new(MouseEventKind::Dragged(MouseButton::Left), 1, 1);
+        let recording = Recording {
+            seed: 7,
+            frames: vec![
+                frame(
+                    0.03125,
+                    (6, 2),
+                    &[Input::Pressed(Key::W), Input::Down(Key::W)],
+                ),
+                frame(
+                    0.09375,
+                    (6, 2),
+                    &[
+                        Input::Down(Key::W),
+                        Input::Mouse(drag.with_modifiers(shift)),
+                    ],
+                ),
+                frame(0.125, (4, 3), &[Input::Released(Key::Esc)]),
+                frame(0.5, (4, 3), &[]),
+            ],
+        };
+        let text = recording.to_text();
+        assert!(text.starts_with("seed 7\nframe 0 0.03125 6 2\npressed w\ndown w\n"));
+        assert!(text.contains("\nmouse dragged left 1 1 shift\nframe 2 0.125 4 3\nreleased esc\n"));
+        assert_eq!(Recording::parse(&text).unwrap(), recording);
+        // keys which can't be bound can still be recorded
+        let unknown = Recording::parse("seed 1\nframe 0 0 1 1\npressed unknown").unwrap();
+        assert_eq!(unknown.frames[0].inputs, [Input::Pressed(Key::Unknown)]);
+
+        let replay = |seed| {
+            let mut recording = recording.clone();
+            recording.seed = seed;
+            let mut screen = HeadlessScreen::new(NoiseState::default(), 1, 1);
+            let frames = screen.replay(&recording);
+            (frames, screen.state().events.clone())
+        };
+        let (frames, events) = replay(7);
+        assert_eq!(
+            events,
+            [
+                "pressed W",
+                "down W",
+                "update 0.03125",
+                "down W",
+                "dragged Left to (1, 1)",
+                "update 0.0625",
+                "released Esc",
+                "update 0.03125",
+                &format!("update {MAX_FRAME_TIME}"),
+            ]
+        );
+        let sizes: Vec<_> = frames.iter().map(|x| (x.width(), x.height())).collect();
+        assert_eq!(sizes, [(6, 2), (6, 2), (4, 3), (4, 3)]);
+        let first: char = StdRng::seed_from_u64(7).gen_range('a'..='z');
+        assert_eq!(frames[0].get(5, 1).glyph, first);
+
+        // the same recording always gives the same frames, but a different seed doesn't
+        assert_eq!(replay(7), (frames.clone(), events.clone()));
+        let (other_frames, other_events) = replay(8);
+        assert_eq!(other_events, events);
+        assert_ne!(other_frames, frames);
+
+        for (source, line) in [
+            ("frame 0 0 1 1", 1),
+            ("seed 1\n\nframe 1 0 1 1", 3),
+            ("seed 1\npressed w", 2),
+            ("seed 1\nframe 0 0 1 1\n# nothing\npressed shift", 4),
+            ("seed 1\nframe 0 0 1 1\nmouse dragged left 1", 3),
+        ] {
+            let error = Recording::parse(source);
+            assert!(
+                matches!(error, Err(FileError::Parse { line: x, .. }) if x == line),
+                "{source:?} gave {error:?}"
+            );
+        }
+    }
+
+    /// Logs its hooks, and moves between scenes named `a`, `b` and `c`
+    struct LoggingScene {
+        name: char,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl LoggingScene {
+        fn new(name: char, log: &Rc<RefCell<Vec<String>>>) -> Self {
+            Self {
+                name,
+                log: log.clone(),
+            }
+        }
+
+        fn log(&self, event: &str) {
+            self.log.borrow_mut().push(format!("{event} {}", self.name));
+        }
+    }
+
+    impl Scene for LoggingScene {
+        fn update(&mut self, _dt: f32, _context: &mut Context) -> Transition {
+            self.log("update");
+            Transition::Stay
+        }
+
+        /// `b` only draws its corner, and only if it's in front of what's there
+        fn draw(&self, framebuffer: &mut Framebuffer) {
+            let width = if self.is_overlay() {
+                1
+            } else {
+                framebuffer.width()
+            };
+            for x in 0..width {
+                if framebuffer.test_depth(x, 0, 1.0) {
+                    framebuffer.get_mut(x, 0).glyph = self.name;
+                }
+            }
+        }
+
+        fn key_pressed(&mut self, key: Key, _context: &mut Context) -> Transition {
+            match (self.name, key) {
+                ('a', Key::P) => Transition::Push(Box::new(LoggingScene::new('b', &self.log))),
+                ('b', Key::R) => Transition::Replace(Box::new(LoggingScene::new('c', &self.log))),
+                (_, Key::O) => Transition::Pop,
+                _ => Transition::Stay,
+            }
+        }
+
+        fn resize(&mut self, width: usize, height: usize, _context: &mut Context) {
+            self.log(&format!("resize {width}x{height}"));
+        }
+
+        fn enter(&mut self, _context: &mut Context) {
+            self.log("enter");
+        }
+
+        fn exit(&mut self, _context: &mut Context) {
+            self.log("exit");
+        }
+
+        fn pause(&mut self, _context: &mut Context) {
+            self.log("pause");
+        }
+
+        fn resume(&mut self, _context: &mut Context) {
+            self.log("resume");
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.name == 'b'
+        }
+    }
+
+    #[test]
+    fn scene_stack() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut screen = HeadlessScreen::new(SceneStack::new(LoggingScene::new('a', &log)), 3, 1);
+        let frames = screen.run(
+            10,
+            &[
+                (1, Input::Pressed(Key::P)),
+                (2, Input::Pressed(Key::R)),
+                (3, Input::Pressed(Key::O)),
+                (4, Input::Pressed(Key::O)),
+            ],
+        );
+        let text: Vec<_> = frames.iter().map(|x| x.to_text()).collect();
+        // the overlay is drawn over the paused scene, even though that's just as close
+        assert_eq!(text, ["aaa\n", "baa\n", "ccc\n", "aaa\n", "aaa\n"]);
+        assert_eq!(
+            *log.borrow(),
+            [
+                "enter a",
+                "resize 3x1 a",
+                "update a",
+                "pause a",
+                "enter b",
+                "resize 3x1 b",
+                "update b",
+                "exit b",
+                "enter c",
+                "resize 3x1 c",
+                "update c",
+                "exit c",
+                "resume a",
+                "update a",
+                // popping the last scene stops instead, once the frame's finished
+                "update a",
+            ]
+        );
+        assert!(!screen.context().is_running());
+        assert_eq!(screen.state().len(), 1);
+        assert_eq!(screen.state().top_as::<LoggingScene>().unwrap().name, 'a');
+        assert!(screen.state().find::<Viewer>().is_none());
+
+        // the demo goes from the menu to the viewer, opens help over it, and back again
+        let menu = SceneStack::new(Menu::new(Bindings::new()));
+        let mut screen = HeadlessScreen::new(menu, 100, 40);
+        let frames = screen.run(
+            5,
+            &[
+                (1, Input::Pressed(Key::Down)),
+                (2, Input::Pressed(Key::Up)),
+                (3, Input::Released(Key::Up)),
+                (4, Input::Pressed(Key::Enter)),
+            ],
+        );
+        assert!(frames[0].to_text().contains("> View model"));
+        assert!(frames[1].to_text().contains("> Help"));
+        assert!(frames[4].get(50, 20).glyph != ' ');
+        let spin = |screen: &HeadlessScreen<SceneStack>| {
+            let viewer = screen.state().find::<Viewer>().unwrap();
+            viewer.mesh.transformation().rotation
+        };
+        let before = spin(&screen);
+        let frames = screen.run(3, &[(0, Input::Pressed(Key::F1))]);
+        assert_eq!(screen.state().len(), 3);
+        assert_eq!(spin(&screen), before);
+        let help = frames[2].to_text();
+        // the paused cube still shows around the edges of the help
+        let line = help.lines().find(|x| x.contains("move_forward")).unwrap();
+        let (outside, inside) = line.split_once('|').unwrap();
+        assert!(!outside.trim().is_empty());
+        assert!(inside.trim_start().starts_with("move_forward    w"));
+
+        screen.run(2, &[(0, Input::Released(Key::Esc))]);
+        assert_ne!(spin(&screen), before);
+        screen.run(1, &[(0, Input::Released(Key::Esc))]);
+        assert!(screen.state().top_as::<Menu>().is_some());
+        screen.run(1, &[(0, Input::Released(Key::Esc))]);
+        assert!(!screen.context().is_running());
+    }
 }
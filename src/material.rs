@@ -0,0 +1,106 @@
+pub mod material {
+    use std::rc::Rc;
+
+    use crate::{colour::colour::Colour, texture::texture::Texture};
+
+    /// How a surface looks, independent of its shape
+    #[derive(Clone, Debug)]
+    pub struct Material {
+        pub name: String,
+        /// Multiplied with each vertex's colour
+        pub diffuse: Colour,
+        /// The colour of highlights, or black for a matte surface
+        pub specular: Colour,
+        /// How tight highlights are. Higher values look glossier.
+        pub shininess: f32,
+        /// Light given off by the surface itself, which is added regardless of lighting
+        pub emissive: Colour,
+        /// How much of the surface's colour comes from mirror reflections, from 0 to 1.
+        /// Only the ray tracer draws reflections.
+        pub reflectivity: f32,
+        /// How much light passes through the surface, from 0 to 1.
+        /// Only the ray tracer draws refraction.
+        pub transparency: f32,
+        /// How much light bends entering the material, such as 1.5 for glass
+        pub refractive_index: f32,
+        /// Glyphs from darkest to brightest, replacing the renderer's ramp
+        pub ramp: Option<String>,
+        /// Sampled at each vertex's texture coordinates and multiplied with the diffuse colour.
+        /// Shared, since many materials often use the same image.
+        pub texture: Option<Rc<Texture>>,
+    }
+
+    impl Material {
+        pub fn new(diffuse: Colour) -> Self {
+            Self {
+                diffuse,
+                ..Default::default()
+            }
+        }
+
+        pub fn with_name(self, name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                ..self
+            }
+        }
+
+        pub fn with_specular(self, specular: Colour, shininess: f32) -> Self {
+            Self {
+                specular,
+                shininess,
+                ..self
+            }
+        }
+
+        pub fn with_emissive(self, emissive: Colour) -> Self {
+            Self { emissive, ..self }
+        }
+
+        pub fn with_reflectivity(self, reflectivity: f32) -> Self {
+            Self {
+                reflectivity,
+                ..self
+            }
+        }
+
+        pub fn with_transparency(self, transparency: f32, refractive_index: f32) -> Self {
+            Self {
+                transparency,
+                refractive_index,
+                ..self
+            }
+        }
+
+        pub fn with_ramp(self, ramp: &str) -> Self {
+            Self {
+                ramp: Some(ramp.to_string()),
+                ..self
+            }
+        }
+
+        pub fn with_texture(self, texture: Rc<Texture>) -> Self {
+            Self {
+                texture: Some(texture),
+                ..self
+            }
+        }
+    }
+
+    impl Default for Material {
+        fn default() -> Self {
+            Self {
+                name: String::new(),
+                diffuse: Colour::WHITE,
+                specular: Colour::BLACK,
+                shininess: 32.0,
+                emissive: Colour::BLACK,
+                reflectivity: 0.0,
+                transparency: 0.0,
+                refractive_index: 1.0,
+                ramp: None,
+                texture: None,
+            }
+        }
+    }
+}
@@ -1,14 +1,14 @@
 #[macro_export]
 macro_rules! matrix {
     ($($($value: expr),+);*) => {
-        crate::matrix::matrix::Matrix::new(
+        $crate::matrix::matrix::Matrix::new(
             [$([$($value as f32),*]),*]
         )
     };
 }
 
 pub mod matrix {
-    use std::ops::{Add, Index, IndexMut, Mul, Sub};
+    use std::ops::{Add, Div, Index, IndexMut, Mul, Neg, Sub};
 
     #[derive(Debug, Clone, Copy)]
     pub struct Matrix<const HEIGHT: usize, const WIDTH: usize> {
@@ -20,6 +20,7 @@ pub mod matrix {
             Self { values }
         }
 
+        #[allow(clippy::result_unit_err)]
         pub fn from_slice(values: &[&[f32]]) -> Result<Self, ()> {
             if values.len() != HEIGHT {
                 return Err(());
@@ -43,6 +44,7 @@ pub mod matrix {
             &self.values
         }
 
+        #[allow(clippy::needless_range_loop)]
         pub fn component_mult(&self, rhs: &Matrix<HEIGHT, WIDTH>) -> Matrix<HEIGHT, WIDTH> {
             let mut values = [[0.0; WIDTH]; HEIGHT];
             for i in 0..HEIGHT {
@@ -52,6 +54,71 @@ pub mod matrix {
             }
             Matrix { values }
         }
+
+        #[allow(clippy::needless_range_loop)]
+        pub fn transpose(&self) -> Matrix<WIDTH, HEIGHT> {
+            let mut values = [[0.0; HEIGHT]; WIDTH];
+            for i in 0..HEIGHT {
+                for j in 0..WIDTH {
+                    values[j][i] = self.values[i][j];
+                }
+            }
+            Matrix { values }
+        }
+    }
+
+    impl<const N: usize> Matrix<N, N> {
+        #[allow(clippy::needless_range_loop)]
+        pub fn identity() -> Self {
+            let mut values = [[0.0; N]; N];
+            for i in 0..N {
+                values[i][i] = 1.0;
+            }
+            Self { values }
+        }
+
+        pub fn trace(&self) -> f32 {
+            (0..N).map(|i| self.values[i][i]).sum()
+        }
+
+        /// Gauss-Jordan elimination with partial pivoting.
+        /// Returns `None` if the matrix is singular
+        pub fn inverse(&self) -> Option<Self> {
+            let mut left = self.values;
+            let mut right = Self::identity().values;
+            for column in 0..N {
+                let pivot = (column..N)
+                    .max_by(|&a, &b| left[a][column].abs().total_cmp(&left[b][column].abs()))?;
+                if left[pivot][column].abs() <= f32::EPSILON {
+                    return None;
+                }
+                left.swap(column, pivot);
+                right.swap(column, pivot);
+
+                let scale = 1.0 / left[column][column];
+                for j in 0..N {
+                    left[column][j] *= scale;
+                    right[column][j] *= scale;
+                }
+                for row in (0..N).filter(|&row| row != column) {
+                    let factor = left[row][column];
+                    for j in 0..N {
+                        left[row][j] -= factor * left[column][j];
+                        right[row][j] -= factor * right[column][j];
+                    }
+                }
+            }
+            Some(Self { values: right })
+        }
+    }
+
+    impl Matrix<3, 3> {
+        pub fn determinant(&self) -> f32 {
+            let m = &self.values;
+            m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+                - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+                + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+        }
     }
 
     /// Index as matrix[y][x]
@@ -160,6 +227,7 @@ pub mod matrix {
     impl<const WIDTH: usize, const HEIGHT: usize> Mul<f32> for Matrix<HEIGHT, WIDTH> {
         type Output = Matrix<HEIGHT, WIDTH>;
 
+        #[allow(clippy::needless_range_loop)]
         fn mul(self, rhs: f32) -> Self::Output {
             let mut result = self.values;
             for i in 0..HEIGHT {
@@ -171,6 +239,62 @@ pub mod matrix {
         }
     }
 
+    /// Affine transformations in homogeneous coordinates
+    impl Matrix<4, 4> {
+        pub fn from_translation(translation: Vector<3>) -> Self {
+            let mut result = Self::identity();
+            for i in 0..3 {
+                result[i][3] = translation[i][0];
+            }
+            result
+        }
+
+        /// Embeds a linear transformation in the top left of an identity matrix
+        pub fn from_linear(linear: Matrix<3, 3>) -> Self {
+            let mut result = Self::identity();
+            for i in 0..3 {
+                for j in 0..3 {
+                    result[i][j] = linear[i][j];
+                }
+            }
+            result
+        }
+
+        /// The top left 3x3 matrix
+        pub fn linear(&self) -> Matrix<3, 3> {
+            let mut result = [[0.0; 3]; 3];
+            for (row, values) in result.iter_mut().zip(&self.values) {
+                row.copy_from_slice(&values[..3]);
+            }
+            Matrix::new(result)
+        }
+
+        pub fn transform_point(&self, point: Vector<3>) -> Vector<3> {
+            (*self * point.extend(1.0)).truncate()
+        }
+
+        /// Transforms a direction, ignoring translation
+        pub fn transform_vector(&self, vector: Vector<3>) -> Vector<3> {
+            (*self * vector.extend(0.0)).truncate()
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize> Div<f32> for Matrix<HEIGHT, WIDTH> {
+        type Output = Matrix<HEIGHT, WIDTH>;
+
+        fn div(self, rhs: f32) -> Self::Output {
+            self * (1.0 / rhs)
+        }
+    }
+
+    impl<const WIDTH: usize, const HEIGHT: usize> Neg for Matrix<HEIGHT, WIDTH> {
+        type Output = Matrix<HEIGHT, WIDTH>;
+
+        fn neg(self) -> Self::Output {
+            self * -1.0
+        }
+    }
+
     pub type Vector<const N: usize> = Matrix<N, 1>;
     pub type Vec2 = Vector<2>;
     pub type Vec3 = Vector<3>;
@@ -200,6 +324,15 @@ pub mod matrix {
         pub fn sqr_length(&self) -> f32 {
             self.as_slice().iter().map(|x| x * x).sum()
         }
+
+        pub fn normalized(&self) -> Vector<N> {
+            *self / self.length()
+        }
+
+        /// The matrix `self * rhs^T`
+        pub fn outer(&self, rhs: &Vector<N>) -> Matrix<N, N> {
+            *self * rhs.transpose()
+        }
     }
 
     impl Vector<1> {
@@ -249,7 +382,31 @@ pub mod matrix {
         }
     }
 
+    impl Vector<4> {
+        pub fn x(&self) -> f32 {
+            self[0][0]
+        }
+        pub fn y(&self) -> f32 {
+            self[1][0]
+        }
+        pub fn z(&self) -> f32 {
+            self[2][0]
+        }
+        pub fn w(&self) -> f32 {
+            self[3][0]
+        }
+
+        /// The first three components, ignoring `w`
+        pub fn truncate(&self) -> Vector<3> {
+            matrix![self.x(); self.y(); self.z()]
+        }
+    }
+
     impl Vector<3> {
+        pub fn extend(&self, w: f32) -> Vector<4> {
+            matrix![self.x(); self.y(); self.z(); w]
+        }
+
         pub fn cross(&self, rhs: &Vector<3>) -> Vector<3> {
             matrix! {
                 self.y()*rhs.z() - self.z()*rhs.y();
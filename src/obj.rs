@@ -0,0 +1,272 @@
+pub mod obj {
+    use std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        rc::Rc,
+    };
+
+    use crate::{
+        colour::colour::Colour,
+        file::file::{lines, FileError},
+        material::material::Material,
+        matrix::matrix::{Vec2, Vec3},
+        shapes::shapes::{Mesh, Triangle, Vertex},
+        texture::texture::{ImageError, Texture},
+    };
+
+    #[derive(Debug)]
+    pub enum ObjError {
+        File(FileError),
+        /// A texture couldn't be loaded
+        Image(ImageError),
+    }
+
+    impl From<FileError> for ObjError {
+        fn from(value: FileError) -> Self {
+            ObjError::File(value)
+        }
+    }
+
+    impl From<std::io::Error> for ObjError {
+        fn from(value: std::io::Error) -> Self {
+            ObjError::File(FileError::Io(value))
+        }
+    }
+
+    impl From<ImageError> for ObjError {
+        fn from(value: ImageError) -> Self {
+            ObjError::Image(value)
+        }
+    }
+
+    /// Loads a Wavefront OBJ model, along with any MTL libraries and textures it refers to,
+    /// which are found relative to the model's folder
+    pub fn load_obj(path: impl AsRef<Path>) -> Result<Mesh, ObjError> {
+        let path = path.as_ref();
+        let folder = folder(path);
+        parse_obj(&fs::read_to_string(path)?, |name| {
+            load_mtl(folder.join(name))
+        })
+    }
+
+    /// Reads a Wavefront OBJ model. `load_library` is given the name from each
+    /// `mtllib` statement, and returns the materials in that library.
+    /// Polygons with more than three corners are split into triangle fans.
+    pub fn parse_obj(
+        source: &str,
+        mut load_library: impl FnMut(&str) -> Result<Vec<Material>, ObjError>,
+    ) -> Result<Mesh, ObjError> {
+        let mut positions: Vec<(Vec3, Option<Colour>)> = Vec::new();
+        let mut uvs: Vec<Vec2> = Vec::new();
+        let mut normals: Vec<Vec3> = Vec::new();
+        let mut library: HashMap<String, Material> = HashMap::new();
+        let mut materials = vec![Material::default()];
+        let mut current = 0;
+        let mut triangles = Vec::new();
+
+        for (line_number, line) in lines(source, &[]) {
+            let error = |message: &str| {
+                ObjError::File(FileError::Parse {
+                    line: line_number,
+                    message: message.to_string(),
+                })
+            };
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let arguments: Vec<&str> = words.collect();
+            match keyword {
+                "v" => {
+                    let values = numbers(&arguments).ok_or(error("expected numbers"))?;
+                    let colour = match values.len() {
+                        3 | 4 => None,
+                        6 => Some(Colour::new(values[3], values[4], values[5])),
+                        _ => return Err(error("expected 3 coordinates")),
+                    };
+                    positions.push((matrix![values[0]; values[1]; values[2]], colour));
+                }
+                "vt" => match numbers(&arguments).as_deref() {
+                    Some(&[u]) => uvs.push(matrix![u; 0]),
+                    Some(&[u, v, ..]) => uvs.push(matrix![u; v]),
+                    _ => return Err(error("expected texture coordinates")),
+                },
+                "vn" => match numbers(&arguments).as_deref() {
+                    Some(&[x, y, z]) => normals.push(matrix![x; y; z]),
+                    _ => return Err(error("expected 3 coordinates")),
+                },
+                "f" => {
+                    let corners = arguments
+                        .iter()
+                        .map(|x| corner(x, &positions, &uvs, &normals))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or(error("face refers to something that doesn't exist"))?;
+                    if corners.len() < 3 {
+                        return Err(error("faces need at least 3 corners"));
+                    }
+                    for i in 1..corners.len() - 1 {
+                        triangles.push(
+                            Triangle::from_vertices([corners[0], corners[i], corners[i + 1]])
+                                .with_material(current),
+                        );
+                    }
+                }
+                "mtllib" => {
+                    for name in arguments {
+                        for material in load_library(name)? {
+                            library.insert(material.name.clone(), material);
+                        }
+                    }
+                }
+                "usemtl" => {
+                    let name = arguments.join(" ");
+                    current = match materials.iter().position(|x| x.name == name) {
+                        Some(index) => index,
+                        None => {
+                            let material =
+                                library.get(&name).ok_or(error("unknown material"))?.clone();
+                            materials.push(material);
+                            materials.len() - 1
+                        }
+                    };
+                }
+                // objects, groups and smoothing groups don't change the mesh
+                _ => {}
+            }
+        }
+
+        let mut mesh = Mesh::new(triangles);
+        for material in materials.into_iter().skip(1) {
+            mesh.add_material(material);
+        }
+        Ok(mesh)
+    }
+
+    /// Loads the materials in a Wavefront MTL library,
+    /// along with textures, which are found relative to the library's folder
+    pub fn load_mtl(path: impl AsRef<Path>) -> Result<Vec<Material>, ObjError> {
+        let path = path.as_ref();
+        let folder = folder(path);
+        let mut textures: HashMap<String, Rc<Texture>> = HashMap::new();
+        parse_mtl(&fs::read_to_string(path)?, |name| {
+            if let Some(texture) = textures.get(name) {
+                return Ok(texture.clone());
+            }
+            let texture = Rc::new(Texture::load(folder.join(name))?);
+            textures.insert(name.to_string(), texture.clone());
+            Ok(texture)
+        })
+    }
+
+    /// Reads a Wavefront MTL library. `load_texture` is given the file name from each
+    /// `map_Kd` statement. As well as the standard statements, `ramp` followed by glyphs
+    /// from darkest to brightest overrides the renderer's glyph ramp. The glyphs are
+    /// everything after the space following `ramp`, so the darkest can be a space too.
+    pub fn parse_mtl(
+        source: &str,
+        mut load_texture: impl FnMut(&str) -> Result<Rc<Texture>, ObjError>,
+    ) -> Result<Vec<Material>, ObjError> {
+        let mut materials: Vec<Material> = Vec::new();
+        // glyph ramps can contain `#`
+        for (line_number, line) in lines(source, &["ramp"]) {
+            let error = |message: &str| {
+                ObjError::File(FileError::Parse {
+                    line: line_number,
+                    message: message.to_string(),
+                })
+            };
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let arguments: Vec<&str> = words.collect();
+            if keyword == "newmtl" {
+                materials.push(Material::default().with_name(&arguments.join(" ")));
+                continue;
+            }
+            let Some(material) = materials.last_mut() else {
+                return Err(error("expected newmtl first"));
+            };
+            let colour = || match numbers(&arguments).as_deref() {
+                Some(&[grey]) => Ok(Colour::grey(grey)),
+                Some(&[r, g, b]) => Ok(Colour::new(r, g, b)),
+                _ => Err(error("expected a colour")),
+            };
+            match keyword {
+                "Kd" => material.diffuse = colour()?,
+                "Ks" => material.specular = colour()?,
+                "Ke" => material.emissive = colour()?,
+                "Ns" | "Ni" | "d" | "Tr" => {
+                    let Some(&[value]) = numbers(&arguments).as_deref() else {
+                        return Err(error("expected a number"));
+                    };
+                    match keyword {
+                        "Ns" => material.shininess = value,
+                        "Ni" => material.refractive_index = value,
+                        // dissolve is the opposite of transparency
+                        "d" => material.transparency = 1.0 - value,
+                        _ => material.transparency = value,
+                    }
+                }
+                // options such as -s come before the file name
+                "map_Kd" => {
+                    let name = arguments.last().ok_or(error("expected a file name"))?;
+                    material.texture = Some(load_texture(name)?);
+                }
+                "ramp" => {
+                    let mut glyphs = line[keyword.len()..].chars();
+                    glyphs.next();
+                    if glyphs.as_str().is_empty() {
+                        return Err(error("expected glyphs"));
+                    }
+                    material.ramp = Some(glyphs.as_str().to_string());
+                }
+                // ambient colour, illumination models and so on aren't supported
+                _ => {}
+            }
+        }
+        Ok(materials)
+    }
+
+    fn numbers(arguments: &[&str]) -> Option<Vec<f32>> {
+        arguments.iter().map(|x| x.parse().ok()).collect()
+    }
+
+    fn folder(path: &Path) -> PathBuf {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    }
+
+    /// A face corner such as `1`, `1/2`, `1//3` or `1/2/3`. Indices count from 1,
+    /// or backwards from the most recent element if they're negative.
+    fn corner(
+        text: &str,
+        positions: &[(Vec3, Option<Colour>)],
+        uvs: &[Vec2],
+        normals: &[Vec3],
+    ) -> Option<Vertex> {
+        fn lookup<T: Copy>(items: &[T], index: &str) -> Option<T> {
+            let index: isize = index.parse().ok()?;
+            let index = if index < 0 {
+                items.len().checked_sub(index.unsigned_abs())?
+            } else {
+                (index as usize).checked_sub(1)?
+            };
+            items.get(index).copied()
+        }
+
+        let mut parts = text.split('/');
+        let (position, colour) = lookup(positions, parts.next()?)?;
+        let mut vertex = Vertex::new(position);
+        if let Some(colour) = colour {
+            vertex = vertex.with_colour(colour);
+        }
+        if let Some(uv) = parts.next().filter(|x| !x.is_empty()) {
+            vertex = vertex.with_uv(lookup(uvs, uv)?);
+        }
+        if let Some(normal) = parts.next().filter(|x| !x.is_empty()) {
+            vertex = vertex.with_normal(Some(lookup(normals, normal)?));
+        }
+        Some(vertex)
+    }
+}
@@ -0,0 +1,209 @@
+pub mod pathtrace {
+    use std::f32::consts::PI;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        camera::camera::Camera,
+        collision::collision::Ray,
+        colour::colour::Colour,
+        light::light::{illuminate_with, Light, LightKind},
+        matrix::matrix::Vec3,
+        raytrace::raytrace::{reflect, refract, TraceScene},
+        texture::texture::Image,
+    };
+
+    /// A Monte-Carlo path tracer which refines its image a little more every frame,
+    /// for as long as the camera and window stay the same
+    pub struct PathTracer {
+        rng: StdRng,
+        /// The sum of every sample taken for each pixel
+        accumulated: Vec<Colour>,
+        samples: usize,
+        width: usize,
+        height: usize,
+        camera: Option<Camera>,
+        /// Samples per pixel added by each call to `render`
+        pub samples_per_frame: usize,
+        /// Paths never bounce more than this many times
+        pub max_bounces: usize,
+        /// Paths can be ended at random, with Russian roulette, after this many bounces
+        pub min_bounces: usize,
+        /// The colour of the sky, which lights anything it can see
+        pub background: Colour,
+    }
+
+    impl PathTracer {
+        pub fn new() -> Self {
+            Self::with_rng(StdRng::from_entropy())
+        }
+
+        /// A path tracer which always takes the same samples, for reproducible images
+        pub fn with_seed(seed: u64) -> Self {
+            Self::with_rng(StdRng::seed_from_u64(seed))
+        }
+
+        fn with_rng(rng: StdRng) -> Self {
+            Self {
+                rng,
+                accumulated: Vec::new(),
+                samples: 0,
+                width: 0,
+                height: 0,
+                camera: None,
+                samples_per_frame: 1,
+                max_bounces: 8,
+                min_bounces: 3,
+                background: Colour::BLACK,
+            }
+        }
+
+        /// How many samples each pixel has had since the image was last reset
+        pub fn samples(&self) -> usize {
+            self.samples
+        }
+
+        /// Throws away the samples so far. This is needed when the scene changes,
+        /// but happens automatically when the camera moves or the size changes.
+        pub fn reset(&mut self) {
+            self.accumulated.fill(Colour::BLACK);
+            self.samples = 0;
+        }
+
+        /// Adds `samples_per_frame` samples to every pixel, starting again if the camera or
+        /// size has changed since last time. Scene lights other than ambient lights are
+        /// sampled directly, since rays can't hit them. Ambient light comes from the
+        /// background instead. The camera's cell aspect is used as the pixel aspect.
+        pub fn render(
+            &mut self,
+            scene: &TraceScene,
+            camera: &Camera,
+            width: usize,
+            height: usize,
+        ) -> Image {
+            if (width, height) != (self.width, self.height) {
+                self.width = width;
+                self.height = height;
+                self.accumulated = vec![Colour::BLACK; width * height];
+                self.samples = 0;
+            }
+            if self.camera != Some(*camera) {
+                self.camera = Some(*camera);
+                self.reset();
+            }
+
+            let lights: Vec<Light> = scene
+                .lights
+                .iter()
+                .filter(|x| x.kind != LightKind::Ambient)
+                .copied()
+                .collect();
+            for _ in 0..self.samples_per_frame {
+                for y in 0..height {
+                    for x in 0..width {
+                        let (jitter_x, jitter_y): (f32, f32) = self.rng.gen();
+                        let ray =
+                            camera.ray(x as f32 + jitter_x, y as f32 + jitter_y, width, height);
+                        let sample = self.radiance(scene, &lights, ray);
+                        let pixel = &mut self.accumulated[y * width + x];
+                        *pixel = *pixel + sample;
+                    }
+                }
+                self.samples += 1;
+            }
+            self.image()
+        }
+
+        /// The average of the samples so far
+        pub fn image(&self) -> Image {
+            let scale = 1.0 / self.samples.max(1) as f32;
+            let pixels = self.accumulated.iter().map(|&x| x * scale).collect();
+            Image::from_pixels(self.width, self.height, pixels).unwrap()
+        }
+
+        /// An estimate of the light coming back along a ray
+        fn radiance(&mut self, scene: &TraceScene, lights: &[Light], mut ray: Ray) -> Colour {
+            let mut colour = Colour::BLACK;
+            // how much of the light found from here on reaches the camera
+            let mut throughput = Colour::WHITE;
+
+            for bounce in 0..self.max_bounces {
+                let Some(hit) = scene.hit(&ray) else {
+                    colour = colour + throughput * self.background;
+                    break;
+                };
+                let material = scene.material(hit.material);
+                colour = colour + throughput * material.emissive;
+
+                let choice: f32 = self.rng.gen();
+                let direction = if choice < material.reflectivity {
+                    reflect(ray.direction, hit.normal)
+                } else if choice < material.reflectivity + material.transparency {
+                    let ratio = if hit.entering {
+                        1.0 / material.refractive_index
+                    } else {
+                        material.refractive_index
+                    };
+                    throughput = throughput * hit.colour;
+                    refract(ray.direction, hit.normal, ratio)
+                        .unwrap_or_else(|| reflect(ray.direction, hit.normal))
+                } else {
+                    // a diffuse bounce, where the cosine in the rendering equation cancels
+                    // with the sampling probability, leaving just the surface colour
+                    let direct = illuminate_with(
+                        lights,
+                        hit.point,
+                        hit.normal,
+                        -ray.direction,
+                        material.shininess,
+                        |i| match scene.occluded(hit.point, &lights[i]) {
+                            true => 0.0,
+                            false => 1.0,
+                        },
+                    );
+                    colour = colour
+                        + throughput
+                            * (hit.colour * direct.diffuse + material.specular * direct.specular);
+                    throughput = throughput * hit.colour;
+                    cosine_hemisphere(&mut self.rng, hit.normal)
+                };
+
+                if bounce + 1 >= self.min_bounces {
+                    // carry on with a probability based on how much light could still arrive,
+                    // making up for the paths which stop by strengthening the ones which don't
+                    let survival = throughput.max_component().min(0.95);
+                    if self.rng.gen::<f32>() >= survival {
+                        break;
+                    }
+                    throughput = throughput * (1.0 / survival);
+                }
+                ray = Ray::new(hit.point, direction);
+            }
+            colour
+        }
+    }
+
+    impl Default for PathTracer {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// A random direction on the hemisphere around `normal`,
+    /// more likely the closer it is to the normal
+    pub fn cosine_hemisphere(rng: &mut impl Rng, normal: Vec3) -> Vec3 {
+        let (u, v): (f32, f32) = rng.gen();
+        let radius = u.sqrt();
+        let angle = 2.0 * PI * v;
+        let (x, y, z) = (radius * angle.cos(), radius * angle.sin(), (1.0 - u).sqrt());
+        // any two directions perpendicular to the normal and each other
+        let helper = if normal.x().abs() > 0.9 {
+            matrix![0; 1; 0]
+        } else {
+            matrix![1; 0; 0]
+        };
+        let tangent = normal.cross(&helper).normalized();
+        let bitangent = normal.cross(&tangent);
+        (tangent * x + bitangent * y + normal * z).normalized()
+    }
+}
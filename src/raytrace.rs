@@ -0,0 +1,297 @@
+pub mod raytrace {
+    use std::ops::Range;
+
+    use crate::{
+        camera::camera::Camera,
+        collision::collision::Ray,
+        colour::colour::Colour,
+        light::light::{illuminate_with, Light},
+        material::material::Material,
+        matrix::matrix::{Matrix, Vec2, Vec3},
+        scene::scene::SceneGraph,
+        shapes::shapes::{BoundingSphere, Mesh},
+        texture::texture::Image,
+    };
+
+    /// How far rays leaving a surface travel before they can hit anything,
+    /// so that they don't hit the surface they left
+    const EPSILON: f32 = 1e-4;
+
+    /// A triangle in world coordinates
+    struct TraceTriangle {
+        positions: [Vec3; 3],
+        normals: [Vec3; 3],
+        colours: [Colour; 3],
+        uvs: [Vec2; 3],
+        /// Index into the scene's materials
+        material: usize,
+    }
+
+    /// Meshes and lights flattened into world coordinates, ready for tracing rays through
+    #[derive(Default)]
+    pub struct TraceScene {
+        triangles: Vec<TraceTriangle>,
+        /// A sphere around each mesh's triangles, so rays can skip whole meshes
+        meshes: Vec<(BoundingSphere, Range<usize>)>,
+        materials: Vec<Material>,
+        pub lights: Vec<Light>,
+    }
+
+    /// Where a ray meets the scene
+    pub struct SurfaceHit {
+        pub point: Vec3,
+        /// Facing back along the ray
+        pub normal: Vec3,
+        /// Whether the ray hit the front of the triangle, going into the mesh
+        pub entering: bool,
+        /// Diffuse colour, including the vertex colours and texture
+        pub colour: Colour,
+        /// Index into the scene's materials
+        pub material: usize,
+    }
+
+    impl TraceScene {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Every mesh and light in the scene graph
+        pub fn from_scene(scene: &SceneGraph) -> Self {
+            let mut result = Self::new();
+            scene.traverse(|_, node, world| {
+                if let Some(mesh) = &node.mesh {
+                    result.add_mesh(mesh, world);
+                }
+            });
+            result.lights = scene.lights();
+            result
+        }
+
+        /// Adds the mesh as if it were attached to something with the world matrix `parent`
+        pub fn add_mesh(&mut self, mesh: &Mesh, parent: &Matrix<4, 4>) {
+            let world = *parent * mesh.transformation().to_matrix();
+            let normal_matrix = world
+                .linear()
+                .inverse()
+                .unwrap_or_else(Matrix::identity)
+                .transpose();
+            let first_material = self.materials.len();
+            self.materials.extend(mesh.materials().iter().cloned());
+
+            let start = self.triangles.len();
+            for (index, triangle) in mesh.posed_triangles().into_iter().enumerate() {
+                let positions = triangle.map(|x| world.transform_point(x.position()));
+                let [a, b, c] = positions;
+                let face_normal = (b - a).cross(&(c - a));
+                if face_normal.sqr_length() == 0.0 {
+                    continue;
+                }
+                // like `Mesh::triangle_material`, falling back to the default material
+                let material = Some(mesh.triangles()[index].material())
+                    .filter(|&x| x < mesh.materials().len())
+                    .unwrap_or(0);
+                self.triangles.push(TraceTriangle {
+                    positions,
+                    normals: triangle.map(|x| {
+                        x.normal()
+                            .map_or(face_normal, |normal| normal_matrix * normal)
+                            .normalized()
+                    }),
+                    colours: triangle.map(|x| x.colour()),
+                    uvs: triangle.map(|x| x.uv()),
+                    material: first_material + material,
+                });
+            }
+            let bounds = BoundingSphere::from_points(
+                self.triangles[start..].iter().flat_map(|x| x.positions),
+            );
+            if let Some(bounds) = bounds {
+                self.meshes.push((bounds, start..self.triangles.len()));
+            }
+        }
+
+        /// The closest triangle the ray hits, along with the hit
+        fn intersect(&self, ray: &Ray) -> Option<(&TraceTriangle, f32, [f32; 3])> {
+            let mut closest: Option<(&TraceTriangle, f32, [f32; 3])> = None;
+            for (bounds, range) in &self.meshes {
+                if !ray.hits_sphere(bounds.centre, bounds.radius) {
+                    continue;
+                }
+                for triangle in &self.triangles[range.clone()] {
+                    let Some(hit) = ray.intersect_triangle(&triangle.positions, EPSILON) else {
+                        continue;
+                    };
+                    if closest.is_none_or(|(_, distance, _)| hit.distance < distance) {
+                        closest = Some((triangle, hit.distance, hit.weights));
+                    }
+                }
+            }
+            closest
+        }
+
+        pub fn material(&self, index: usize) -> &Material {
+            &self.materials[index]
+        }
+
+        /// The closest surface the ray hits
+        pub fn hit(&self, ray: &Ray) -> Option<SurfaceHit> {
+            let (triangle, distance, weights) = self.intersect(ray)?;
+            let material = &self.materials[triangle.material];
+            let [a, b, c] = triangle.positions;
+            let entering = (b - a).cross(&(c - a)).dot(&ray.direction) < 0.0;
+            let normal = (0..3)
+                .fold(matrix![0; 0; 0], |acc, i| {
+                    acc + triangle.normals[i] * weights[i]
+                })
+                .normalized();
+            let mut colour = (0..3).fold(Colour::BLACK, |acc, i| {
+                acc + triangle.colours[i] * weights[i]
+            }) * material.diffuse;
+            if let Some(texture) = &material.texture {
+                let uv = (0..3).fold(matrix![0; 0], |acc, i| acc + triangle.uvs[i] * weights[i]);
+                colour = colour * texture.sample(uv.x(), uv.y());
+            }
+            Some(SurfaceHit {
+                point: ray.at(distance),
+                normal: if entering { normal } else { -normal },
+                entering,
+                colour,
+                material: triangle.material,
+            })
+        }
+
+        /// Whether anything lies between `point` and the light
+        pub fn occluded(&self, point: Vec3, light: &Light) -> bool {
+            let Some((to_light, _)) = light.incident(point) else {
+                return false;
+            };
+            let ray = Ray::new(point, to_light);
+            self.intersect(&ray)
+                .is_some_and(|(_, distance, _)| distance < light.distance(point))
+        }
+    }
+
+    /// A Whitted-style ray tracer, with hard shadows, mirror reflections and refraction
+    pub struct RayTracer {
+        /// Lights used for scenes without any lights of their own
+        pub lights: Vec<Light>,
+        /// How many times a ray can be reflected or refracted
+        pub max_depth: usize,
+        /// The colour of rays which don't hit anything
+        pub background: Colour,
+        pub shadows: bool,
+    }
+
+    impl Default for RayTracer {
+        fn default() -> Self {
+            Self {
+                lights: vec![
+                    Light::ambient(Colour::WHITE, 0.15),
+                    Light::directional(matrix![-1; -2; -3], Colour::WHITE, 0.85),
+                ],
+                max_depth: 4,
+                background: Colour::BLACK,
+                shadows: true,
+            }
+        }
+    }
+
+    impl RayTracer {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Traces a ray through the centre of each pixel. The camera's cell aspect is used
+        /// as the pixel aspect, so it should be 1 when rendering square pixels to a file.
+        pub fn render(
+            &self,
+            scene: &TraceScene,
+            camera: &Camera,
+            width: usize,
+            height: usize,
+        ) -> Image {
+            let mut image = Image::new(width, height);
+            for y in 0..height {
+                for x in 0..width {
+                    let ray = camera.ray(x as f32 + 0.5, y as f32 + 0.5, width, height);
+                    image.set(x, y, self.trace(scene, &ray, 0));
+                }
+            }
+            image
+        }
+
+        /// The colour seen along a ray which has already bounced `depth` times
+        pub fn trace(&self, scene: &TraceScene, ray: &Ray, depth: usize) -> Colour {
+            let Some(hit) = scene.hit(ray) else {
+                return self.background;
+            };
+            let material = &scene.materials[hit.material];
+            let lights = if scene.lights.is_empty() {
+                &self.lights
+            } else {
+                &scene.lights
+            };
+            let lighting = illuminate_with(
+                lights,
+                hit.point,
+                hit.normal,
+                -ray.direction,
+                material.shininess,
+                |i| match self.shadows && scene.occluded(hit.point, &lights[i]) {
+                    true => 0.0,
+                    false => 1.0,
+                },
+            );
+            let local = hit.colour * lighting.diffuse
+                + material.specular * lighting.specular
+                + material.emissive;
+            if depth >= self.max_depth {
+                return local;
+            }
+
+            let mirror = (material.reflectivity > 0.0).then(|| {
+                let reflected = Ray::new(hit.point, reflect(ray.direction, hit.normal));
+                self.trace(scene, &reflected, depth + 1)
+            });
+            let refracted = (material.transparency > 0.0).then(|| {
+                let ratio = if hit.entering {
+                    1.0 / material.refractive_index
+                } else {
+                    material.refractive_index
+                };
+                // total internal reflection happens when there's no refracted direction
+                let direction = refract(ray.direction, hit.normal, ratio)
+                    .unwrap_or_else(|| reflect(ray.direction, hit.normal));
+                self.trace(scene, &Ray::new(hit.point, direction), depth + 1)
+            });
+
+            let surface = (1.0 - material.reflectivity - material.transparency).max(0.0);
+            let mut colour = local * surface;
+            if let Some(mirror) = mirror {
+                colour = colour + mirror * material.reflectivity;
+            }
+            if let Some(refracted) = refracted {
+                // light passing through is tinted by the surface
+                colour = colour + refracted * hit.colour * material.transparency;
+            }
+            colour
+        }
+    }
+
+    /// `direction` bounced off a surface with the unit normal `normal`
+    pub fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+        direction - normal * (2.0 * direction.dot(&normal))
+    }
+
+    /// `direction` bent by Snell's law passing into a surface with the unit normal `normal`,
+    /// where `ratio` is the refractive index being left over the one being entered.
+    /// Returns `None` for total internal reflection.
+    pub fn refract(direction: Vec3, normal: Vec3, ratio: f32) -> Option<Vec3> {
+        let cos_in = -direction.dot(&normal);
+        let sin_sqr_out = ratio * ratio * (1.0 - cos_in * cos_in);
+        if sin_sqr_out > 1.0 {
+            return None;
+        }
+        Some(direction * ratio + normal * (ratio * cos_in - (1.0 - sin_sqr_out).sqrt()))
+    }
+}
@@ -0,0 +1,197 @@
+pub mod recording {
+    use std::{fmt::Write, fs, path::Path};
+
+    use ruscii::keyboard::Key;
+
+    use crate::{
+        bindings::bindings::{key_from_name, key_name},
+        file::file::{lines, FileError},
+        screen::screen::{Input, MouseButton, MouseEvent, MouseEventKind},
+    };
+
+    /// Everything that happened during one frame
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct RecordedFrame {
+        /// Seconds from the start of the recording to the start of the frame
+        pub time: f64,
+        /// The width and height of the window in cells
+        pub size: (usize, usize),
+        /// Input in the order the state was sent it
+        pub inputs: Vec<Input>,
+    }
+
+    /// The input a game state was given, frame by frame, with the seed its random
+    /// numbers came from, so that it can be run again exactly the same way.
+    ///
+    /// Recording files start with the seed, then have a line for each frame with its
+    /// number, time and size, followed by a line for each input during it:
+    /// ```text
+    /// seed 1234
+    /// frame 0 0 80 24
+    /// frame 1 0.0334 80 24
+    /// pressed w
+    /// down w
+    /// mouse dragged left 10 5 shift
+    /// ```
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Recording {
+        pub seed: u64,
+        pub frames: Vec<RecordedFrame>,
+    }
+
+    impl Recording {
+        pub fn new(seed: u64) -> Self {
+            Self {
+                seed,
+                frames: Vec::new(),
+            }
+        }
+
+        pub fn load(path: impl AsRef<Path>) -> Result<Self, FileError> {
+            Self::parse(&fs::read_to_string(path)?)
+        }
+
+        pub fn save(&self, path: impl AsRef<Path>) -> Result<(), FileError> {
+            Ok(fs::write(path, self.to_text())?)
+        }
+
+        /// Seconds from the start of the recording to the start of the last frame
+        pub fn duration(&self) -> f64 {
+            self.frames.last().map_or(0.0, |x| x.time)
+        }
+
+        pub fn parse(source: &str) -> Result<Self, FileError> {
+            let mut recording: Option<Recording> = None;
+            for (line_number, line) in lines(source, &[]) {
+                let error = |message: &str| FileError::Parse {
+                    line: line_number,
+                    message: message.to_string(),
+                };
+                let words: Vec<&str> = line.split_whitespace().collect();
+                let Some(recording) = &mut recording else {
+                    let ["seed", seed] = words.as_slice() else {
+                        return Err(error("expected the seed first"));
+                    };
+                    recording = Some(Recording::new(
+                        seed.parse().map_err(|_| error("expected a whole number"))?,
+                    ));
+                    continue;
+                };
+                if let ["frame", number, time, width, height] = words.as_slice() {
+                    if number.parse() != Ok(recording.frames.len()) {
+                        return Err(error("frames should be numbered in order from 0"));
+                    }
+                    let number = |x: &str| x.parse().map_err(|_| error("expected a number"));
+                    recording.frames.push(RecordedFrame {
+                        time: time.parse().map_err(|_| error("expected a number"))?,
+                        size: (number(width)?, number(height)?),
+                        inputs: Vec::new(),
+                    });
+                    continue;
+                }
+                let Some(frame) = recording.frames.last_mut() else {
+                    return Err(error("expected a frame before any input"));
+                };
+                frame
+                    .inputs
+                    .push(parse_input(&words).ok_or(error("unknown input"))?);
+            }
+            recording.ok_or(FileError::Parse {
+                line: source.lines().count(),
+                message: "expected the seed".to_string(),
+            })
+        }
+
+        pub fn to_text(&self) -> String {
+            let mut text = format!("seed {}\n", self.seed);
+            for (number, frame) in self.frames.iter().enumerate() {
+                let (width, height) = frame.size;
+                writeln!(text, "frame {number} {} {width} {height}", frame.time).unwrap();
+                for input in &frame.inputs {
+                    writeln!(text, "{}", input_text(input)).unwrap();
+                }
+            }
+            text
+        }
+    }
+
+    fn input_text(input: &Input) -> String {
+        match input {
+            Input::Pressed(key) => format!("pressed {}", key_name(*key)),
+            Input::Released(key) => format!("released {}", key_name(*key)),
+            Input::Down(key) => format!("down {}", key_name(*key)),
+            Input::Mouse(event) => {
+                let button = |x: MouseButton| format!("{x:?}").to_lowercase();
+                let kind = match event.kind {
+                    MouseEventKind::Down(x) => format!("down {}", button(x)),
+                    MouseEventKind::Up(x) => format!("up {}", button(x)),
+                    MouseEventKind::Moved => "moved".to_string(),
+                    MouseEventKind::Dragged(x) => format!("dragged {}", button(x)),
+                    MouseEventKind::Scrolled(x) => format!("scrolled {x}"),
+                };
+                let mut text = format!("mouse {kind} {} {}", event.position.0, event.position.1);
+                let modifiers = &event.modifiers;
+                for (held, name) in [
+                    (modifiers.shift, "shift"),
+                    (modifiers.control, "control"),
+                    (modifiers.alt, "alt"),
+                ] {
+                    if held {
+                        write!(text, " {name}").unwrap();
+                    }
+                }
+                text
+            }
+        }
+    }
+
+    /// Like `key_from_name`, but also reads back keys that couldn't be told apart,
+    /// which can't be bound but can still be recorded
+    fn recorded_key(name: &str) -> Option<Key> {
+        match name {
+            "unknown" => Some(Key::Unknown),
+            _ => key_from_name(name),
+        }
+    }
+
+    fn parse_input(words: &[&str]) -> Option<Input> {
+        match words {
+            ["pressed", key] => Some(Input::Pressed(recorded_key(key)?)),
+            ["released", key] => Some(Input::Released(recorded_key(key)?)),
+            ["down", key] => Some(Input::Down(recorded_key(key)?)),
+            ["mouse", kind, rest @ ..] => {
+                let button = |x: &str| match x {
+                    "left" => Some(MouseButton::Left),
+                    "right" => Some(MouseButton::Right),
+                    "middle" => Some(MouseButton::Middle),
+                    _ => None,
+                };
+                let (kind, rest) = match (*kind, rest) {
+                    ("down", [x, rest @ ..]) => (MouseEventKind::Down(button(x)?), rest),
+                    ("up", [x, rest @ ..]) => (MouseEventKind::Up(button(x)?), rest),
+                    ("dragged", [x, rest @ ..]) => (MouseEventKind::Dragged(button(x)?), rest),
+                    ("scrolled", [x, rest @ ..]) => {
+                        (MouseEventKind::Scrolled(x.parse().ok()?), rest)
+                    }
+                    ("moved", rest) => (MouseEventKind::Moved, rest),
+                    _ => return None,
+                };
+                let [column, row, modifiers @ ..] = rest else {
+                    return None;
+                };
+                let mut event = MouseEvent::new(kind, column.parse().ok()?, row.parse().ok()?);
+                for modifier in modifiers {
+                    let held = match *modifier {
+                        "shift" => &mut event.modifiers.shift,
+                        "control" => &mut event.modifiers.control,
+                        "alt" => &mut event.modifiers.alt,
+                        _ => return None,
+                    };
+                    *held = true;
+                }
+                Some(Input::Mouse(event))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,364 @@
+pub mod render {
+    use crate::{
+        camera::camera::Camera,
+        colour::colour::{Colour, ColourMode},
+        light::light::{illuminate_with, Light},
+        matrix::matrix::{Matrix, Vec2, Vec3},
+        screen::screen::Framebuffer,
+        shadow::shadow::{ShadowMap, ShadowSettings},
+        shapes::shapes::{BoundingSphere, Mesh},
+        texture::texture::Image,
+    };
+
+    /// Glyphs from darkest to brightest
+    pub const GLYPH_RAMP: &str = ".:-=+*#%@";
+
+    /// The glyph for a brightness from 0 to 1, or a space if the ramp is empty
+    pub fn glyph(ramp: &str, intensity: f32) -> char {
+        let count = ramp.chars().count();
+        if count == 0 {
+            return ' ';
+        }
+        let index = (intensity.clamp(0.0, 1.0) * (count - 1) as f32).round() as usize;
+        ramp.chars().nth(index).unwrap_or(' ')
+    }
+
+    /// The distance to the closest surface drawn so far in each cell
+    #[derive(Clone, Debug)]
+    pub struct DepthBuffer {
+        width: usize,
+        height: usize,
+        values: Vec<f32>,
+    }
+
+    impl DepthBuffer {
+        pub fn new(width: usize, height: usize) -> Self {
+            Self {
+                width,
+                height,
+                values: vec![f32::INFINITY; width * height],
+            }
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        /// Resizes and clears the buffer
+        pub fn resize(&mut self, width: usize, height: usize) {
+            *self = Self::new(width, height);
+        }
+
+        pub fn clear(&mut self) {
+            self.values.fill(f32::INFINITY);
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> f32 {
+            self.values[y * self.width + x]
+        }
+
+        /// Records `depth` if it's closer than anything already in the cell,
+        /// returning whether it was
+        pub fn test_and_set(&mut self, x: usize, y: usize, depth: f32) -> bool {
+            let value = &mut self.values[y * self.width + x];
+            if depth < *value {
+                *value = depth;
+                true
+            } else {
+                false
+            }
+        }
+    }
+
+    /// Calls `fragment` for every cell whose centre is inside the triangle, in either winding.
+    /// `points` are columns, rows and distances from the camera, as from `Camera::project`.
+    /// The fragment gets the cell's position and depth, and barycentric weights for
+    /// interpolating vertex attributes which have been corrected for perspective.
+    pub fn rasterize_triangle(
+        points: [Vec3; 3],
+        width: usize,
+        height: usize,
+        mut fragment: impl FnMut(usize, usize, f32, [f32; 3]),
+    ) {
+        let edge = |a: &Vec3, b: &Vec3, x: f32, y: f32| {
+            (b.x() - a.x()) * (y - a.y()) - (b.y() - a.y()) * (x - a.x())
+        };
+        let [a, b, c] = &points;
+        let area = edge(a, b, c.x(), c.y());
+        if area == 0.0 {
+            return;
+        }
+
+        let range = |values: [f32; 3], limit: usize| {
+            let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let start = (min - 0.5).ceil().max(0.0) as usize;
+            let end = ((max - 0.5).floor() + 1.0).clamp(0.0, limit as f32) as usize;
+            start..end
+        };
+        let inverse_depths = points.map(|x| 1.0 / x.z());
+
+        for y in range(points.map(|x| x.y()), height) {
+            for x in range(points.map(|x| x.x()), width) {
+                let (centre_x, centre_y) = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [
+                    edge(b, c, centre_x, centre_y) / area,
+                    edge(c, a, centre_x, centre_y) / area,
+                    edge(a, b, centre_x, centre_y) / area,
+                ];
+                if weights.iter().any(|&x| x < 0.0) {
+                    continue;
+                }
+                // 1/depth is linear in screen space, but the attributes themselves aren't
+                let inverse_depth: f32 = (0..3).map(|i| weights[i] * inverse_depths[i]).sum();
+                let depth = 1.0 / inverse_depth;
+                let corrected = [0, 1, 2].map(|i| weights[i] * inverse_depths[i] * depth);
+                fragment(x, y, depth, corrected);
+            }
+        }
+    }
+
+    /// A vertex on its way through the pipeline, which can be interpolated when clipping
+    #[derive(Clone, Copy, Debug)]
+    struct PipelineVertex {
+        world: Vec3,
+        view: Vec3,
+        normal: Vec3,
+        colour: Colour,
+        uv: Vec2,
+    }
+
+    impl PipelineVertex {
+        fn lerp(&self, other: &PipelineVertex, t: f32) -> Self {
+            Self {
+                world: self.world + (other.world - self.world) * t,
+                view: self.view + (other.view - self.view) * t,
+                normal: self.normal + (other.normal - self.normal) * t,
+                colour: self.colour.lerp(other.colour, t),
+                uv: self.uv + (other.uv - self.uv) * t,
+            }
+        }
+    }
+
+    /// Cuts off the parts of a polygon closer to the camera than `near`
+    fn clip_near(polygon: &[PipelineVertex], near: f32) -> Vec<PipelineVertex> {
+        let distance = |x: &PipelineVertex| -x.view.z() - near;
+        let mut result = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let (current_distance, next_distance) = (distance(current), distance(next));
+            if current_distance >= 0.0 {
+                result.push(*current);
+            }
+            if (current_distance >= 0.0) != (next_distance >= 0.0) {
+                let t = current_distance / (current_distance - next_distance);
+                result.push(current.lerp(next, t));
+            }
+        }
+        result
+    }
+
+    /// Draws shaded, coloured triangles into a framebuffer, using its depth
+    pub struct Renderer {
+        pub colour_mode: ColourMode,
+        /// Glyphs from darkest to brightest
+        pub ramp: String,
+        /// Lights used when drawing meshes on their own. Scenes with lights replace these.
+        pub lights: Vec<Light>,
+        /// Directional and spot lights cast shadows when this is set
+        pub shadows: Option<ShadowSettings>,
+        /// One for each light, built by `cast_shadows`
+        shadow_maps: Vec<Option<ShadowMap>>,
+        /// Skip triangles which face away from the camera
+        pub cull_back_faces: bool,
+    }
+
+    impl Default for Renderer {
+        fn default() -> Self {
+            Self {
+                colour_mode: ColourMode::detect(),
+                ramp: GLYPH_RAMP.to_string(),
+                lights: vec![
+                    Light::ambient(Colour::WHITE, 0.15),
+                    Light::directional(matrix![-1; -2; -3], Colour::WHITE, 0.85),
+                ],
+                shadows: None,
+                shadow_maps: Vec::new(),
+                cull_back_faces: true,
+            }
+        }
+    }
+
+    impl Renderer {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Draws a cell with a glyph for `brightness` from `ramp`, or the renderer's own
+        /// ramp if there isn't one, in the closest colour the terminal has to `colour`
+        pub fn plot(
+            &self,
+            framebuffer: &mut Framebuffer,
+            x: usize,
+            y: usize,
+            brightness: f32,
+            colour: Colour,
+            ramp: Option<&str>,
+        ) {
+            let cell = framebuffer.get_mut(x, y);
+            cell.glyph = glyph(ramp.unwrap_or(&self.ramp), brightness);
+            cell.foreground = self.colour_mode.quantise(colour);
+            cell.true_colour = (self.colour_mode == ColourMode::TrueColour).then_some(colour);
+        }
+
+        /// Draws an image with one pixel in each cell, such as from the ray or path tracer.
+        /// Brighter pixels get brighter glyphs, and black pixels are left empty.
+        pub fn draw_image(&self, framebuffer: &mut Framebuffer, image: &Image) {
+            for y in 0..image.height().min(framebuffer.height()) {
+                for x in 0..image.width().min(framebuffer.width()) {
+                    let colour = image.get(x, y);
+                    let brightness = colour.max_component();
+                    if brightness <= 0.0 {
+                        continue;
+                    }
+                    self.plot(framebuffer, x, y, brightness, colour, None);
+                }
+            }
+        }
+
+        /// The shadow map for the light at `index`, if it casts shadows
+        pub fn shadow_map(&self, index: usize) -> Option<&ShadowMap> {
+            self.shadow_maps.get(index)?.as_ref()
+        }
+
+        /// Builds a shadow map for each light from the meshes which cast shadows,
+        /// along with their parents' world matrices. This has to happen after the lights
+        /// are set up, and before drawing anything which should be shadowed.
+        /// Does nothing if shadows are turned off.
+        pub fn cast_shadows(&mut self, casters: &[(&Mesh, Matrix<4, 4>)]) {
+            self.shadow_maps.clear();
+            let Some(settings) = self.shadows else {
+                return;
+            };
+            let points = casters.iter().flat_map(|(mesh, parent)| {
+                let world = *parent * mesh.transformation().to_matrix();
+                mesh.posed_triangles()
+                    .into_iter()
+                    .flatten()
+                    .map(move |x| world.transform_point(x.position()))
+            });
+            let Some(bounds) = BoundingSphere::from_points(points) else {
+                return;
+            };
+            self.shadow_maps = self
+                .lights
+                .iter()
+                .map(|light| {
+                    let mut map = ShadowMap::new(light, &bounds, settings.resolution)?;
+                    for (mesh, parent) in casters {
+                        map.add_mesh(mesh, parent);
+                    }
+                    Some(map)
+                })
+                .collect();
+        }
+
+        /// Draws the mesh as if it were attached to something with the world matrix `parent`
+        pub fn draw_mesh(
+            &mut self,
+            framebuffer: &mut Framebuffer,
+            camera: &Camera,
+            mesh: &Mesh,
+            parent: &Matrix<4, 4>,
+        ) {
+            let world = *parent * mesh.transformation().to_matrix();
+            let normal_matrix = world
+                .linear()
+                .inverse()
+                .unwrap_or_else(Matrix::identity)
+                .transpose();
+            let view = camera.view_matrix() * world;
+            let (width, height) = (framebuffer.width(), framebuffer.height());
+
+            for (index, triangle) in mesh.posed_triangles().into_iter().enumerate() {
+                let material = mesh.triangle_material(index);
+                let positions = triangle.map(|x| view.transform_point(x.position()));
+                let [a, b, c] = positions;
+                let face_normal = (b - a).cross(&(c - a));
+                if face_normal.sqr_length() == 0.0
+                    || (self.cull_back_faces && face_normal.dot(&a) >= 0.0)
+                {
+                    continue;
+                }
+                let local_face_normal = {
+                    let [a, b, c] = triangle.map(|x| x.position());
+                    (b - a).cross(&(c - a))
+                };
+
+                let vertices: Vec<_> = triangle
+                    .iter()
+                    .zip(positions)
+                    .map(|(vertex, view)| PipelineVertex {
+                        world: world.transform_point(vertex.position()),
+                        view,
+                        normal: (normal_matrix * vertex.normal().unwrap_or(local_face_normal))
+                            .normalized(),
+                        colour: vertex.colour() * material.diffuse,
+                        uv: vertex.uv(),
+                    })
+                    .collect();
+                let polygon = clip_near(&vertices, camera.near());
+
+                for i in 1..polygon.len().saturating_sub(1) {
+                    let corners = [polygon[0], polygon[i], polygon[i + 1]];
+                    let points = corners.map(|x| camera.project_view(x.view, width, height));
+                    rasterize_triangle(points, width, height, |x, y, depth, weights| {
+                        if !framebuffer.test_depth(x, y, depth) {
+                            return;
+                        }
+                        let interpolate = |f: &dyn Fn(&PipelineVertex) -> Vec3| {
+                            (0..3)
+                                .fold(matrix![0; 0; 0], |acc, i| acc + f(&corners[i]) * weights[i])
+                        };
+                        let normal = interpolate(&|x| x.normal).normalized();
+                        let mut colour = (0..3)
+                            .fold(Colour::BLACK, |acc, i| acc + corners[i].colour * weights[i]);
+                        if let Some(texture) = &material.texture {
+                            let uv = (0..3)
+                                .fold(matrix![0; 0], |acc, i| acc + corners[i].uv * weights[i]);
+                            colour = colour * texture.sample(uv.x(), uv.y());
+                        }
+
+                        let point = interpolate(&|x| x.world);
+                        let to_camera = (camera.position() - point).normalized();
+                        let lighting = illuminate_with(
+                            &self.lights,
+                            point,
+                            normal,
+                            to_camera,
+                            material.shininess,
+                            |i| match (self.shadow_map(i), &self.shadows) {
+                                (Some(map), Some(settings)) => map.visibility(point, settings),
+                                _ => 1.0,
+                            },
+                        );
+                        let diffuse = colour * lighting.diffuse;
+                        let extra = material.specular * lighting.specular + material.emissive;
+                        self.plot(
+                            framebuffer,
+                            x,
+                            y,
+                            lighting.diffuse.max_component() + extra.max_component(),
+                            diffuse + extra,
+                            material.ramp.as_deref(),
+                        );
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+pub mod scene {
+    use crate::{
+        camera::camera::Camera,
+        light::light::Light,
+        matrix::matrix::Matrix,
+        render::render::Renderer,
+        screen::screen::Framebuffer,
+        shapes::shapes::{Mesh, Transformation},
+    };
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct NodeId(usize);
+
+    pub struct Node {
+        name: String,
+        /// Relative to the parent node, or to the world for root nodes
+        pub transformation: Transformation,
+        pub mesh: Option<Mesh>,
+        /// Positioned and pointed by the node's world transformation
+        pub light: Option<Light>,
+        parent: Option<NodeId>,
+        children: Vec<NodeId>,
+    }
+
+    impl Node {
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        pub fn parent(&self) -> Option<NodeId> {
+            self.parent
+        }
+
+        pub fn children(&self) -> &[NodeId] {
+            &self.children
+        }
+    }
+
+    /// A tree of nodes, each positioned relative to its parent.
+    /// Removed nodes leave a gap, so a `NodeId` never refers to a different node later.
+    #[derive(Default)]
+    pub struct SceneGraph {
+        nodes: Vec<Option<Node>>,
+        roots: Vec<NodeId>,
+    }
+
+    impl SceneGraph {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Adds a node under `parent`, or at the root if `parent` is `None`.
+        /// Fails if `parent` isn't in the graph.
+        #[allow(clippy::result_unit_err)]
+        pub fn add(
+            &mut self,
+            parent: Option<NodeId>,
+            name: &str,
+            transformation: Transformation,
+            mesh: Option<Mesh>,
+        ) -> Result<NodeId, ()> {
+            if parent.is_some_and(|x| self.get(x).is_none()) {
+                return Err(());
+            }
+            let id = NodeId(self.nodes.len());
+            self.nodes.push(Some(Node {
+                name: name.to_string(),
+                transformation,
+                mesh,
+                light: None,
+                parent,
+                children: Vec::new(),
+            }));
+            self.siblings_mut(parent).push(id);
+            Ok(id)
+        }
+
+        /// Adds a node holding a light, like `add`
+        #[allow(clippy::result_unit_err)]
+        pub fn add_light(
+            &mut self,
+            parent: Option<NodeId>,
+            name: &str,
+            transformation: Transformation,
+            light: Light,
+        ) -> Result<NodeId, ()> {
+            let id = self.add(parent, name, transformation, None)?;
+            self.nodes[id.0].as_mut().unwrap().light = Some(light);
+            Ok(id)
+        }
+
+        /// Removes a node along with all of its descendants, returning the node itself
+        pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+            let node = self.nodes.get_mut(id.0)?.take()?;
+            self.siblings_mut(node.parent).retain(|&x| x != id);
+            let mut descendants = node.children.clone();
+            while let Some(child) = descendants.pop() {
+                if let Some(child) = self.nodes[child.0].take() {
+                    descendants.extend(child.children);
+                }
+            }
+            Some(node)
+        }
+
+        /// Moves a node (and its descendants) under `parent`, keeping its local transformation.
+        /// Fails if either node is missing, or if `parent` is the node or one of its descendants.
+        #[allow(clippy::result_unit_err)]
+        pub fn reparent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<(), ()> {
+            let old_parent = self.get(id).ok_or(())?.parent;
+            if let Some(parent) = parent {
+                self.get(parent).ok_or(())?;
+                if self.ancestors(parent).any(|x| x == id) {
+                    return Err(());
+                }
+            }
+            self.siblings_mut(old_parent).retain(|&x| x != id);
+            self.siblings_mut(parent).push(id);
+            self.nodes[id.0].as_mut().unwrap().parent = parent;
+            Ok(())
+        }
+
+        /// The first node called `name`, searching depth first from the roots
+        pub fn find(&self, name: &str) -> Option<NodeId> {
+            let mut result = None;
+            self.traverse(|id, node, _| {
+                if result.is_none() && node.name == name {
+                    result = Some(id);
+                }
+            });
+            result
+        }
+
+        pub fn get(&self, id: NodeId) -> Option<&Node> {
+            self.nodes.get(id.0)?.as_ref()
+        }
+
+        pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+            self.nodes.get_mut(id.0)?.as_mut()
+        }
+
+        pub fn roots(&self) -> &[NodeId] {
+            &self.roots
+        }
+
+        /// The node itself, followed by its parent, grandparent and so on
+        pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+            std::iter::successors(self.get(id).map(|_| id), |&x| self.get(x)?.parent)
+        }
+
+        /// Converts coordinates local to the node into world coordinates
+        pub fn world_matrix(&self, id: NodeId) -> Option<Matrix<4, 4>> {
+            self.get(id)?;
+            Some(self.ancestors(id).fold(Matrix::identity(), |acc, x| {
+                self.nodes[x.0].as_ref().unwrap().transformation.to_matrix() * acc
+            }))
+        }
+
+        /// Visits every node depth first, parents before children,
+        /// along with its world matrix
+        pub fn traverse<'a>(&'a self, mut visit: impl FnMut(NodeId, &'a Node, &Matrix<4, 4>)) {
+            let mut stack: Vec<_> = self
+                .roots
+                .iter()
+                .rev()
+                .map(|&x| (x, Matrix::identity()))
+                .collect();
+            while let Some((id, parent)) = stack.pop() {
+                let node = self.nodes[id.0].as_ref().unwrap();
+                let world = parent * node.transformation.to_matrix();
+                visit(id, node, &world);
+                stack.extend(node.children.iter().rev().map(|&x| (x, world)));
+            }
+        }
+
+        /// Every light in the scene, in world coordinates
+        pub fn lights(&self) -> Vec<Light> {
+            let mut lights = Vec::new();
+            self.traverse(|_, node, world| {
+                if let Some(light) = &node.light {
+                    lights.push(light.transformed(world));
+                }
+            });
+            lights
+        }
+
+        /// Draws every mesh, lit by the scene's lights, with every mesh casting shadows
+        /// if the renderer has them turned on.
+        /// If the scene has no lights, the renderer's own lights are used instead.
+        pub fn draw(
+            &self,
+            renderer: &mut Renderer,
+            framebuffer: &mut Framebuffer,
+            camera: &Camera,
+        ) {
+            let lights = self.lights();
+            let previous =
+                (!lights.is_empty()).then(|| std::mem::replace(&mut renderer.lights, lights));
+            let mut meshes = Vec::new();
+            self.traverse(|_, node, world| {
+                if let Some(mesh) = &node.mesh {
+                    meshes.push((mesh, *world));
+                }
+            });
+            renderer.cast_shadows(&meshes);
+            for (mesh, world) in &meshes {
+                renderer.draw_mesh(framebuffer, camera, mesh, world);
+            }
+            if let Some(previous) = previous {
+                renderer.lights = previous;
+            }
+        }
+
+        fn siblings_mut(&mut self, parent: Option<NodeId>) -> &mut Vec<NodeId> {
+            match parent {
+                Some(parent) => &mut self.nodes[parent.0].as_mut().unwrap().children,
+                None => &mut self.roots,
+            }
+        }
+    }
+}
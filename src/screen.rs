@@ -1,17 +1,518 @@
 pub mod screen {
-    use ruscii::{app::App, drawing::Pencil, keyboard::Key, terminal::Window};
+    use std::{
+        fmt::Write,
+        panic::{self, AssertUnwindSafe},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crossterm::event::{self as terminal_event, KeyModifiers};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use ruscii::{
+        drawing::Pencil,
+        keyboard::Key,
+        terminal::{Color, Window},
+    };
+
+    use crate::{
+        colour::colour::Colour,
+        input::input::TerminalInput,
+        recording::recording::{RecordedFrame, Recording},
+    };
+
+    /// Frames longer than this, such as after the program has been suspended,
+    /// only move time on by this much, in seconds
+    pub const MAX_FRAME_TIME: f32 = 0.25;
 
     pub trait GameState {
-        fn update(&mut self, app_state: &mut ruscii::app::State);
-        fn draw(&self, pencil: &mut Pencil, win_x: usize, win_y: usize);
-        fn key_pressed(&mut self, key: Key, app_state: &mut ruscii::app::State);
-        fn key_released(&mut self, key: Key, app_state: &mut ruscii::app::State);
-        fn key_down(&mut self, key: Key, app_state: &mut ruscii::app::State);
+        /// Called once a frame with the seconds since the last frame
+        fn update(&mut self, dt: f32, context: &mut Context);
+        fn draw(&self, framebuffer: &mut Framebuffer);
+        fn key_pressed(&mut self, key: Key, context: &mut Context);
+        fn key_released(&mut self, key: Key, context: &mut Context);
+        fn key_down(&mut self, key: Key, context: &mut Context);
+
+        /// Called before the first frame with the size of the window in cells,
+        /// and again whenever it changes. The framebuffer has already been resized.
+        fn resize(&mut self, _width: usize, _height: usize, _context: &mut Context) {}
+
+        /// With a fixed timestep, called before `update` as many times as needed to catch up
+        /// with the time that's passed, each time moving on by exactly `step` seconds.
+        /// `Context::alpha` gives how far the current time is between the last two steps.
+        fn fixed_update(&mut self, _step: f32, _context: &mut Context) {}
+
+        /// Mouse positions are the column and row of a cell
+        fn mouse_down(
+            &mut self,
+            _button: MouseButton,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+        }
+
+        fn mouse_up(
+            &mut self,
+            _button: MouseButton,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+        }
+
+        /// The mouse moved without any buttons held
+        fn mouse_moved(
+            &mut self,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+        }
+
+        /// The mouse moved while `button` was held
+        fn mouse_dragged(
+            &mut self,
+            _button: MouseButton,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+        }
+
+        /// The scroll wheel turned by `amount` steps, positive away from the user
+        fn mouse_scrolled(
+            &mut self,
+            _amount: i32,
+            _position: (usize, usize),
+            _modifiers: Modifiers,
+            _context: &mut Context,
+        ) {
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MouseButton {
+        Left,
+        Right,
+        Middle,
+    }
+
+    /// Keys held down during a mouse event
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Modifiers {
+        pub shift: bool,
+        pub control: bool,
+        pub alt: bool,
+    }
+
+    impl Modifiers {
+        pub fn any(&self) -> bool {
+            self.shift || self.control || self.alt
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MouseEventKind {
+        Down(MouseButton),
+        Up(MouseButton),
+        Moved,
+        Dragged(MouseButton),
+        /// Steps of the scroll wheel, positive away from the user
+        Scrolled(i32),
+    }
+
+    /// Something the mouse did at a cell
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct MouseEvent {
+        pub kind: MouseEventKind,
+        pub position: (usize, usize),
+        pub modifiers: Modifiers,
+    }
+
+    impl MouseEvent {
+        pub fn new(kind: MouseEventKind, column: usize, row: usize) -> Self {
+            Self {
+                kind,
+                position: (column, row),
+                modifiers: Modifiers::default(),
+            }
+        }
+
+        pub fn with_modifiers(self, modifiers: Modifiers) -> Self {
+            Self { modifiers, ..self }
+        }
+
+        /// Converts a mouse event read from the terminal
+        pub fn from_terminal(event: terminal_event::MouseEvent) -> Self {
+            let button = |x| match x {
+                terminal_event::MouseButton::Left => MouseButton::Left,
+                terminal_event::MouseButton::Right => MouseButton::Right,
+                terminal_event::MouseButton::Middle => MouseButton::Middle,
+            };
+            let kind = match event.kind {
+                terminal_event::MouseEventKind::Down(x) => MouseEventKind::Down(button(x)),
+                terminal_event::MouseEventKind::Up(x) => MouseEventKind::Up(button(x)),
+                terminal_event::MouseEventKind::Drag(x) => MouseEventKind::Dragged(button(x)),
+                terminal_event::MouseEventKind::Moved => MouseEventKind::Moved,
+                terminal_event::MouseEventKind::ScrollUp => MouseEventKind::Scrolled(1),
+                terminal_event::MouseEventKind::ScrollDown => MouseEventKind::Scrolled(-1),
+            };
+            let modifiers = Modifiers {
+                shift: event.modifiers.contains(KeyModifiers::SHIFT),
+                control: event.modifiers.contains(KeyModifiers::CONTROL),
+                alt: event.modifiers.contains(KeyModifiers::ALT),
+            };
+            Self::new(kind, event.column as usize, event.row as usize).with_modifiers(modifiers)
+        }
+
+        /// Calls the state's method for this event
+        pub fn send(&self, state: &mut impl GameState, context: &mut Context) {
+            let (position, modifiers) = (self.position, self.modifiers);
+            match self.kind {
+                MouseEventKind::Down(x) => state.mouse_down(x, position, modifiers, context),
+                MouseEventKind::Up(x) => state.mouse_up(x, position, modifiers, context),
+                MouseEventKind::Moved => state.mouse_moved(position, modifiers, context),
+                MouseEventKind::Dragged(x) => state.mouse_dragged(x, position, modifiers, context),
+                MouseEventKind::Scrolled(x) => {
+                    state.mouse_scrolled(x, position, modifiers, context)
+                }
+            }
+        }
+    }
+
+    /// Runs simulation code in steps of the same length, however long frames take
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct FixedTimestep {
+        /// Seconds per step, which must be more than 0
+        pub step: f32,
+        /// Frames never run more steps than this, so a slow simulation can't fall ever
+        /// further behind. The time left over is dropped.
+        pub max_steps: usize,
+        /// Time which has passed but hasn't been stepped through yet
+        accumulator: f32,
+    }
+
+    impl FixedTimestep {
+        /// Panics unless `step` is more than 0, since time would never move on
+        pub fn new(step: f32) -> Self {
+            assert!(step > 0.0, "fixed timestep must be more than 0, not {step}");
+            Self {
+                step,
+                max_steps: 8,
+                accumulator: 0.0,
+            }
+        }
+
+        /// Adds `dt` seconds, returning how many steps to run
+        pub fn advance(&mut self, dt: f32) -> usize {
+            self.accumulator += dt;
+            let steps = (self.accumulator / self.step).floor() as usize;
+            self.accumulator -= steps as f32 * self.step;
+            if steps > self.max_steps {
+                self.accumulator = 0.0;
+            }
+            steps.min(self.max_steps)
+        }
+
+        /// How far from 0 to 1 the current time is past the last step, for interpolating
+        /// between the last two simulated states
+        pub fn alpha(&self) -> f32 {
+            (self.accumulator / self.step).clamp(0.0, 1.0)
+        }
+    }
+
+    /// What a game state can find out about, and change about, whatever is running it
+    #[derive(Clone, Debug)]
+    pub struct Context {
+        running: bool,
+        step: usize,
+        size: (usize, usize),
+        frame_time: f32,
+        fixed_timestep: Option<FixedTimestep>,
+        seed: u64,
+        rng: StdRng,
+    }
+
+    impl Default for Context {
+        fn default() -> Self {
+            Self {
+                running: true,
+                step: 0,
+                size: (0, 0),
+                frame_time: 0.0,
+                fixed_timestep: None,
+                seed: 0,
+                rng: StdRng::seed_from_u64(0),
+            }
+        }
+    }
+
+    impl Context {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Ends the program after this frame
+        pub fn stop(&mut self) {
+            self.running = false;
+        }
+
+        pub fn is_running(&self) -> bool {
+            self.running
+        }
+
+        /// How many frames have finished so far
+        pub fn step(&self) -> usize {
+            self.step
+        }
+
+        /// The width and height of the window in cells
+        pub fn size(&self) -> (usize, usize) {
+            self.size
+        }
+
+        /// How long the last frame took in seconds, measured from the start of one frame
+        /// to the start of the next, before being limited to `MAX_FRAME_TIME`
+        pub fn frame_time(&self) -> f32 {
+            self.frame_time
+        }
+
+        pub fn fixed_timestep(&self) -> Option<&FixedTimestep> {
+            self.fixed_timestep.as_ref()
+        }
+
+        /// How far the current time is between the last two fixed steps,
+        /// or 0 without a fixed timestep
+        pub fn alpha(&self) -> f32 {
+            self.fixed_timestep.map_or(0.0, |x| x.alpha())
+        }
+
+        /// Where the random numbers from `rng` started
+        pub fn seed(&self) -> u64 {
+            self.seed
+        }
+
+        /// Random numbers which come out the same again when a recording is replayed,
+        /// so states should use this rather than seeding their own
+        pub fn rng(&mut self) -> &mut StdRng {
+            &mut self.rng
+        }
+
+        fn reseed(&mut self, seed: u64) {
+            self.seed = seed;
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+
+        /// Moves time on by a frame which took `frame_time` seconds,
+        /// running any fixed steps and then the state's update
+        fn advance(&mut self, frame_time: f32, state: &mut impl GameState) {
+            self.frame_time = frame_time;
+            let dt = frame_time.min(MAX_FRAME_TIME);
+            if let Some(timestep) = &mut self.fixed_timestep {
+                let step = timestep.step;
+                for _ in 0..timestep.advance(dt) {
+                    state.fixed_update(step, self);
+                }
+            }
+            state.update(dt, self);
+        }
+
+        /// Resizes the framebuffer and tells the state, if the window has changed size
+        fn track_size(
+            &mut self,
+            size: (usize, usize),
+            framebuffer: &mut Framebuffer,
+            state: &mut impl GameState,
+        ) {
+            if self.size == size && (framebuffer.width(), framebuffer.height()) == size {
+                return;
+            }
+            self.size = size;
+            framebuffer.resize(size.0, size.1);
+            state.resize(size.0, size.1, self);
+        }
+    }
+
+    /// One character on the screen
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Cell {
+        pub glyph: char,
+        pub foreground: Color,
+        pub background: Color,
+        /// The exact foreground colour, for terminals with 24 bit colour.
+        /// `to_ansi` uses it instead of `foreground`, which ruscii still draws with.
+        pub true_colour: Option<Colour>,
+        /// The distance from the camera to whatever was drawn here,
+        /// which is infinite until something is
+        pub depth: f32,
+    }
+
+    impl Default for Cell {
+        /// An empty cell, in ruscii's default colours
+        fn default() -> Self {
+            Self {
+                glyph: ' ',
+                foreground: Color::White,
+                background: Color::Black,
+                true_colour: None,
+                depth: f32::INFINITY,
+            }
+        }
+    }
+
+    /// A grid of cells which everything is drawn into, before being shown in the terminal
+    /// or turned into text
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Framebuffer {
+        width: usize,
+        height: usize,
+        cells: Vec<Cell>,
+    }
+
+    impl Framebuffer {
+        pub fn new(width: usize, height: usize) -> Self {
+            Self {
+                width,
+                height,
+                cells: vec![Cell::default(); width * height],
+            }
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        /// Resizes and clears the framebuffer
+        pub fn resize(&mut self, width: usize, height: usize) {
+            *self = Self::new(width, height);
+        }
+
+        pub fn clear(&mut self) {
+            self.cells.fill(Cell::default());
+        }
+
+        /// Forgets the depth of everything drawn so far, so anything drawn next goes on top
+        pub fn clear_depth(&mut self) {
+            for cell in &mut self.cells {
+                cell.depth = f32::INFINITY;
+            }
+        }
+
+        /// Every cell, a row at a time from the top
+        pub fn cells(&self) -> &[Cell] {
+            &self.cells
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> &Cell {
+            &self.cells[y * self.width + x]
+        }
+
+        pub fn get_mut(&mut self, x: usize, y: usize) -> &mut Cell {
+            &mut self.cells[y * self.width + x]
+        }
+
+        /// Records `depth` if it's closer than anything already in the cell,
+        /// returning whether it was
+        pub fn test_depth(&mut self, x: usize, y: usize, depth: f32) -> bool {
+            let cell = self.get_mut(x, y);
+            if depth < cell.depth {
+                cell.depth = depth;
+                true
+            } else {
+                false
+            }
+        }
+
+        /// Writes text from left to right starting at a cell, on top of everything,
+        /// cutting it off at the edge
+        pub fn draw_text(&mut self, x: usize, y: usize, text: &str, foreground: Color) {
+            if y >= self.height {
+                return;
+            }
+            for (x, glyph) in (x..self.width).zip(text.chars()) {
+                let cell = self.get_mut(x, y);
+                cell.glyph = glyph;
+                cell.foreground = foreground;
+                cell.true_colour = None;
+            }
+        }
+
+        /// Draws every cell with the pencil, with the top left cell at the pencil's origin
+        pub fn blit(&self, pencil: &mut Pencil) {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let cell = self.get(x, y);
+                    pencil
+                        .set_foreground(cell.foreground)
+                        .set_background(cell.background)
+                        .draw_char(
+                            cell.glyph,
+                            ruscii::spatial::Vec2 {
+                                x: x as i32,
+                                y: y as i32,
+                            },
+                        );
+                }
+            }
+        }
+
+        /// Just the glyphs, with a line for each row
+        pub fn to_text(&self) -> String {
+            let mut text = String::with_capacity((self.width + 1) * self.height);
+            for row in self.cells.chunks(self.width.max(1)).take(self.height) {
+                text.extend(row.iter().map(|x| x.glyph));
+                text.push('\n');
+            }
+            text
+        }
+
+        /// The glyphs with escape codes for their colours, which a terminal can show
+        /// directly. Foregrounds use 24 bit colour where cells have it, and xterm colours
+        /// otherwise. Each line resets the colours at the end.
+        pub fn to_ansi(&self) -> String {
+            let mut text = String::new();
+            for row in self.cells.chunks(self.width.max(1)).take(self.height) {
+                let mut current = None;
+                for cell in row {
+                    let foreground = match cell.true_colour {
+                        Some(colour) => {
+                            let [r, g, b] = colour.to_rgb8();
+                            format!("2;{r};{g};{b}")
+                        }
+                        None => format!("5;{}", cell.foreground.code()),
+                    };
+                    let colours = (foreground, cell.background.code());
+                    if current.as_ref() != Some(&colours) {
+                        write!(text, "\x1b[38;{}m\x1b[48;5;{}m", colours.0, colours.1).unwrap();
+                        current = Some(colours);
+                    }
+                    text.push(cell.glyph);
+                }
+                text.push_str("\x1b[0m\n");
+            }
+            text
+        }
     }
 
     pub struct Screen<State: GameState> {
         window: Window,
         state: State,
+        /// Frames per second to aim for. Frames are never shown faster than this,
+        /// but can be slower.
+        pub target_fps: u32,
+        pub fixed_timestep: Option<FixedTimestep>,
+        /// Seeds `Context::rng`, which is seeded at random without one
+        pub seed: Option<u64>,
+        /// Input from the last run, if it's being recorded
+        recording: Option<Recording>,
+        /// Input to use instead of the keyboard and mouse
+        replay: Option<Recording>,
     }
 
     impl<State: GameState> Screen<State> {
@@ -19,35 +520,287 @@ pub mod screen {
             Self {
                 window: Window::default(),
                 state,
+                target_fps: 30,
+                fixed_timestep: None,
+                seed: None,
+                recording: None,
+                replay: None,
+            }
+        }
+
+        pub fn with_target_fps(self, target_fps: u32) -> Self {
+            Self { target_fps, ..self }
+        }
+
+        /// Calls `GameState::fixed_update` every `step` seconds
+        pub fn with_fixed_timestep(self, step: f32) -> Self {
+            Self {
+                fixed_timestep: Some(FixedTimestep::new(step)),
+                ..self
+            }
+        }
+
+        pub fn with_seed(self, seed: u64) -> Self {
+            Self {
+                seed: Some(seed),
+                ..self
+            }
+        }
+
+        /// Records the input each time the screen runs, for `recording` to return afterwards
+        pub fn with_recording(self) -> Self {
+            Self {
+                recording: Some(Recording::default()),
+                ..self
+            }
+        }
+
+        /// Runs on the recorded input, seed, frame times and window sizes instead of
+        /// the real ones, stopping at the end of the recording.
+        /// The window isn't resized, so the frames may not fit.
+        pub fn with_replay(self, recording: Recording) -> Self {
+            Self {
+                replay: Some(recording),
+                ..self
             }
         }
 
+        /// The input from the last run, if it was recorded
+        pub fn recording(&self) -> Option<&Recording> {
+            self.recording.as_ref()
+        }
+
+        /// Runs until the state stops it
         pub fn run(&mut self) {
-            let mut app = App::default();
-            let window_size = app.window().size();
-
-            app.run(|app_state: &mut ruscii::app::State, window: &mut Window| {
-                for key_event in app_state.keyboard().last_key_events().clone() {
-                    match key_event {
-                        ruscii::keyboard::KeyEvent::Pressed(x) => {
-                            self.state.key_pressed(x, app_state)
+            let frame_duration = Duration::from_secs_f64(1.0 / self.target_fps.max(1) as f64);
+            let mut framebuffer = Framebuffer::new(0, 0);
+            let mut context = Context::new();
+            context.fixed_timestep = self.fixed_timestep;
+            let seed = match &self.replay {
+                Some(replay) => replay.seed,
+                None => self.seed.unwrap_or_else(rand::random),
+            };
+            context.reseed(seed);
+            if let Some(recording) = &mut self.recording {
+                *recording = Recording::new(seed);
+            }
+            let mut replayed = self.replay.as_ref().map(|x| x.frames.iter());
+            let start = Instant::now();
+            let mut last_time = 0.0;
+
+            self.window.open();
+            // put the terminal back however the state stops
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut input = replayed.is_none().then(TerminalInput::new);
+                for step in 0.. {
+                    let frame_start = Instant::now();
+                    // the window's canvas follows the terminal's size
+                    self.window.clear();
+                    let frame = match (&mut replayed, &mut input) {
+                        (Some(frames), _) => match frames.next() {
+                            Some(frame) => frame.clone(),
+                            None => break,
+                        },
+                        (None, input) => {
+                            let size = self.window.size();
+                            RecordedFrame {
+                                time: start.elapsed().as_secs_f64(),
+                                size: (size.x.max(0) as usize, size.y.max(0) as usize),
+                                inputs: input
+                                    .as_mut()
+                                    .map(TerminalInput::inputs)
+                                    .unwrap_or_default(),
+                            }
                         }
-                        ruscii::keyboard::KeyEvent::Released(x) => {
-                            self.state.key_released(x, app_state)
+                    };
+                    // both come from the same timestamps, so replays get exactly the same times
+                    let frame_time = (frame.time - last_time) as f32;
+                    last_time = frame.time;
+                    context.step = step;
+                    context.track_size(frame.size, &mut framebuffer, &mut self.state);
+                    for input in &frame.inputs {
+                        input.send(&mut self.state, &mut context);
+                    }
+                    if let Some(recording) = &mut self.recording {
+                        recording.frames.push(frame);
+                    }
+
+                    context.advance(frame_time, &mut self.state);
+                    framebuffer.clear();
+                    self.state.draw(&mut framebuffer);
+                    framebuffer.blit(&mut Pencil::new(self.window.canvas_mut()));
+                    self.window.draw();
+                    if !context.is_running() {
+                        break;
+                    }
+                    if let Some(rest) = frame_duration.checked_sub(frame_start.elapsed()) {
+                        thread::sleep(rest);
+                    }
+                }
+            }));
+            self.window.close();
+            if let Err(error) = result {
+                panic::resume_unwind(error);
+            }
+        }
+    }
+
+    /// A scripted or recorded event
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Input {
+        /// The key goes down, and is held until it's released
+        Pressed(Key),
+        Released(Key),
+        /// The key is held for just this frame, without being pressed or released
+        Down(Key),
+        Mouse(MouseEvent),
+    }
+
+    impl Input {
+        /// Calls the state's method for this input
+        pub fn send(&self, state: &mut impl GameState, context: &mut Context) {
+            match *self {
+                Input::Pressed(key) => state.key_pressed(key, context),
+                Input::Released(key) => state.key_released(key, context),
+                Input::Down(key) => state.key_down(key, context),
+                Input::Mouse(event) => event.send(state, context),
+            }
+        }
+    }
+
+    /// Runs a game state without a terminal, feeding it scripted input
+    /// and keeping every frame it draws. Time is pretend too, so runs are repeatable.
+    pub struct HeadlessScreen<State: GameState> {
+        state: State,
+        context: Context,
+        framebuffer: Framebuffer,
+        /// The size of the pretend window
+        size: (usize, usize),
+        /// How many seconds each frame pretends to take
+        pub frame_time: f32,
+        /// Keys which have been pressed and not released yet, in the order they were pressed
+        held: Vec<Key>,
+    }
+
+    impl<State: GameState> HeadlessScreen<State> {
+        pub fn new(state: State, width: usize, height: usize) -> Self {
+            Self {
+                state,
+                context: Context::new(),
+                framebuffer: Framebuffer::new(0, 0),
+                size: (width, height),
+                frame_time: 1.0 / 30.0,
+                held: Vec::new(),
+            }
+        }
+
+        /// Calls `GameState::fixed_update` every `step` seconds of pretend time
+        pub fn with_fixed_timestep(mut self, step: f32) -> Self {
+            self.context.fixed_timestep = Some(FixedTimestep::new(step));
+            self
+        }
+
+        /// Seeds `Context::rng`, which is seeded with 0 otherwise
+        pub fn with_seed(mut self, seed: u64) -> Self {
+            self.context.reseed(seed);
+            self
+        }
+
+        pub fn state(&self) -> &State {
+            &self.state
+        }
+
+        pub fn state_mut(&mut self) -> &mut State {
+            &mut self.state
+        }
+
+        pub fn context(&self) -> &Context {
+            &self.context
+        }
+
+        /// Changes the size of the pretend window,
+        /// which the state finds out about at the start of the next frame
+        pub fn resize(&mut self, width: usize, height: usize) {
+            self.size = (width, height);
+        }
+
+        /// Runs `frames` frames the same way `Screen::run` does, or fewer if the state stops.
+        /// `script` holds input and the frame it happens on, counting from the first
+        /// frame of this run. Like a real keyboard, pressed keys are held down every frame
+        /// until they're released. Mouse events are sent after the keys.
+        pub fn run(&mut self, frames: usize, script: &[(usize, Input)]) -> Vec<Framebuffer> {
+            let mut captured = Vec::with_capacity(frames);
+            for frame in 0..frames {
+                if !self.context.is_running() {
+                    break;
+                }
+                self.context
+                    .track_size(self.size, &mut self.framebuffer, &mut self.state);
+                let events = script.iter().filter(|(x, _)| *x == frame).map(|(_, x)| *x);
+                let mut down = Vec::new();
+                for event in events {
+                    match event {
+                        Input::Pressed(key) => {
+                            if !self.held.contains(&key) {
+                                self.held.push(key);
+                            }
+                            self.state.key_pressed(key, &mut self.context);
+                        }
+                        Input::Released(key) => {
+                            self.held.retain(|&x| x != key);
+                            self.state.key_released(key, &mut self.context);
                         }
+                        Input::Down(key) => down.push(key),
+                        Input::Mouse(_) => {}
                     }
                 }
 
-                for key in app_state.keyboard().get_keys_down() {
-                    self.state.key_down(key, app_state);
+                down.retain(|x| !self.held.contains(x));
+                for key in self.held.iter().chain(&down) {
+                    self.state.key_down(*key, &mut self.context);
                 }
 
-                self.state.update(app_state);
-                let mut pencil = Pencil::new(window.canvas_mut());
-                let pencil = &mut pencil;
-                self.state
-                    .draw(pencil, window_size.x as usize, window_size.y as usize);
-            });
+                for (_, input) in script.iter().filter(|(x, _)| *x == frame) {
+                    if let Input::Mouse(event) = input {
+                        event.send(&mut self.state, &mut self.context);
+                    }
+                }
+
+                captured.push(self.finish_frame(self.frame_time));
+            }
+            captured
+        }
+
+        /// Runs the frames of a recording with its input, seed, frame times and window
+        /// sizes, or fewer if the state stops. This gives the same frames as the run
+        /// which was recorded, as long as the state started out the same.
+        pub fn replay(&mut self, recording: &Recording) -> Vec<Framebuffer> {
+            self.context.reseed(recording.seed);
+            let mut captured = Vec::with_capacity(recording.frames.len());
+            let mut last_time = 0.0;
+            for frame in &recording.frames {
+                if !self.context.is_running() {
+                    break;
+                }
+                self.size = frame.size;
+                self.context
+                    .track_size(self.size, &mut self.framebuffer, &mut self.state);
+                for input in &frame.inputs {
+                    input.send(&mut self.state, &mut self.context);
+                }
+                captured.push(self.finish_frame((frame.time - last_time) as f32));
+                last_time = frame.time;
+            }
+            captured
+        }
+
+        /// Moves time on, then draws and returns the frame
+        fn finish_frame(&mut self, frame_time: f32) -> Framebuffer {
+            self.context.advance(frame_time, &mut self.state);
+            self.framebuffer.clear();
+            self.state.draw(&mut self.framebuffer);
+            self.context.step += 1;
+            self.framebuffer.clone()
         }
     }
 }
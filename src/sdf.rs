@@ -0,0 +1,338 @@
+pub mod sdf {
+    use crate::{
+        camera::camera::Camera,
+        colour::colour::Colour,
+        light::light::{illuminate_with, Light},
+        material::material::Material,
+        matrix::matrix::Vec3,
+        render::render::Renderer,
+        screen::screen::Framebuffer,
+        shapes::shapes::Transformation,
+    };
+
+    #[derive(Clone, Debug)]
+    pub enum SdfKind {
+        Sphere {
+            radius: f32,
+        },
+        /// Centred on the origin
+        Cuboid {
+            size: Vec3,
+        },
+        /// Lying flat around the y axis
+        Torus {
+            major_radius: f32,
+            minor_radius: f32,
+        },
+        /// The ground at y = 0, solid underneath
+        Plane,
+        Union(Box<Sdf>, Box<Sdf>),
+        Intersection(Box<Sdf>, Box<Sdf>),
+        /// The first shape with the second cut out of it
+        Subtraction(Box<Sdf>, Box<Sdf>),
+        /// Like a union, but blending the shapes together where they're
+        /// within `smoothness` of each other
+        SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    }
+
+    /// A shape described by a signed distance function,
+    /// which is negative inside the shape and positive outside
+    #[derive(Clone, Debug)]
+    pub struct Sdf {
+        pub kind: SdfKind,
+        pub transformation: Transformation,
+        /// Combined shapes use their children's materials instead
+        pub material: Material,
+    }
+
+    impl Sdf {
+        pub fn new(kind: SdfKind) -> Self {
+            Self {
+                kind,
+                transformation: Transformation::default(),
+                material: Material::default(),
+            }
+        }
+
+        pub fn sphere(radius: f32) -> Self {
+            Self::new(SdfKind::Sphere { radius })
+        }
+
+        pub fn cuboid(size: Vec3) -> Self {
+            Self::new(SdfKind::Cuboid { size })
+        }
+
+        pub fn torus(major_radius: f32, minor_radius: f32) -> Self {
+            Self::new(SdfKind::Torus {
+                major_radius,
+                minor_radius,
+            })
+        }
+
+        pub fn plane() -> Self {
+            Self::new(SdfKind::Plane)
+        }
+
+        pub fn union(self, other: Sdf) -> Self {
+            Self::new(SdfKind::Union(Box::new(self), Box::new(other)))
+        }
+
+        pub fn intersection(self, other: Sdf) -> Self {
+            Self::new(SdfKind::Intersection(Box::new(self), Box::new(other)))
+        }
+
+        pub fn subtraction(self, other: Sdf) -> Self {
+            Self::new(SdfKind::Subtraction(Box::new(self), Box::new(other)))
+        }
+
+        pub fn smooth_union(self, other: Sdf, smoothness: f32) -> Self {
+            Self::new(SdfKind::SmoothUnion(
+                Box::new(self),
+                Box::new(other),
+                smoothness,
+            ))
+        }
+
+        pub fn with_transformation(self, transformation: Transformation) -> Self {
+            Self {
+                transformation,
+                ..self
+            }
+        }
+
+        pub fn with_material(self, material: Material) -> Self {
+            Self { material, ..self }
+        }
+
+        /// The signed distance from `point` to the surface. With non-uniform scaling
+        /// this is only a lower bound, which is still safe for sphere tracing.
+        pub fn distance(&self, point: Vec3) -> f32 {
+            self.sample(point).0
+        }
+
+        /// The signed distance to the surface, along with the material of the closest shape
+        pub fn sample(&self, point: Vec3) -> (f32, &Material) {
+            let local = self.transformation.inverse_transform(point);
+            let scale = self.transformation.scale;
+            let scale = scale.x().abs().min(scale.y().abs()).min(scale.z().abs());
+            let (distance, material) = match &self.kind {
+                SdfKind::Sphere { radius } => (local.length() - radius, &self.material),
+                SdfKind::Cuboid { size } => {
+                    let [x, y, z] = local.as_slice();
+                    let [x, y, z] = [x.abs(), y.abs(), z.abs()];
+                    let [sx, sy, sz] = (*size * 0.5).as_slice();
+                    let q = matrix![x - sx; y - sy; z - sz];
+                    let outside = matrix![q.x().max(0.0); q.y().max(0.0); q.z().max(0.0)];
+                    let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+                    (outside.length() + inside, &self.material)
+                }
+                SdfKind::Torus {
+                    major_radius,
+                    minor_radius,
+                } => {
+                    let around = (local.x() * local.x() + local.z() * local.z()).sqrt();
+                    let ring = around - major_radius;
+                    (
+                        (ring * ring + local.y() * local.y()).sqrt() - minor_radius,
+                        &self.material,
+                    )
+                }
+                SdfKind::Plane => (local.y(), &self.material),
+                SdfKind::Union(a, b) => {
+                    let (a, b) = (a.sample(local), b.sample(local));
+                    if a.0 <= b.0 {
+                        a
+                    } else {
+                        b
+                    }
+                }
+                SdfKind::Intersection(a, b) => {
+                    let (a, b) = (a.sample(local), b.sample(local));
+                    if a.0 >= b.0 {
+                        a
+                    } else {
+                        b
+                    }
+                }
+                SdfKind::Subtraction(a, b) => {
+                    let (a, b) = (a.sample(local), b.sample(local));
+                    if a.0 >= -b.0 {
+                        a
+                    } else {
+                        (-b.0, b.1)
+                    }
+                }
+                SdfKind::SmoothUnion(a, b, smoothness) => {
+                    let (a, b) = (a.sample(local), b.sample(local));
+                    let k = smoothness.max(f32::EPSILON);
+                    let h = (0.5 + 0.5 * (b.0 - a.0) / k).clamp(0.0, 1.0);
+                    let distance = b.0 + (a.0 - b.0) * h - k * h * (1.0 - h);
+                    (distance, if h >= 0.5 { a.1 } else { b.1 })
+                }
+            };
+            (distance * scale, material)
+        }
+
+        /// The direction the distance increases fastest, which is the surface normal
+        /// at points on the surface
+        pub fn normal(&self, point: Vec3) -> Vec3 {
+            let step = 1e-3;
+            let difference =
+                |offset: Vec3| self.distance(point + offset) - self.distance(point - offset);
+            matrix![
+                difference(matrix![step; 0; 0]);
+                difference(matrix![0; step; 0]);
+                difference(matrix![0; 0; step])
+            ]
+            .normalized()
+        }
+    }
+
+    /// Draws signed distance functions by sphere tracing rays from the camera
+    pub struct Raymarcher {
+        /// Lights the shapes are shaded with
+        pub lights: Vec<Light>,
+        /// Rays give up after this many steps
+        pub max_steps: usize,
+        /// Rays give up after travelling this far
+        pub max_distance: f32,
+        /// Rays stop this close to a surface
+        pub epsilon: f32,
+        /// How sharp shadows are. Higher values give harder edges, and 0 turns shadows off.
+        pub shadow_sharpness: f32,
+        /// How much ambient occlusion darkens creases, and 0 turns it off
+        pub occlusion: f32,
+    }
+
+    impl Default for Raymarcher {
+        fn default() -> Self {
+            Self {
+                lights: vec![
+                    Light::ambient(Colour::WHITE, 0.15),
+                    Light::directional(matrix![-1; -2; -3], Colour::WHITE, 0.85),
+                ],
+                max_steps: 128,
+                max_distance: 100.0,
+                epsilon: 1e-3,
+                shadow_sharpness: 8.0,
+                occlusion: 1.0,
+            }
+        }
+    }
+
+    impl Raymarcher {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// How far along the ray the surface is, if it's hit within `max_distance`
+        pub fn march(&self, sdf: &Sdf, origin: Vec3, direction: Vec3) -> Option<f32> {
+            let mut travelled = 0.0;
+            for _ in 0..self.max_steps {
+                let distance = sdf.distance(origin + direction * travelled);
+                if distance < self.epsilon {
+                    return Some(travelled);
+                }
+                travelled += distance;
+                if travelled > self.max_distance {
+                    break;
+                }
+            }
+            None
+        }
+
+        /// How much light gets from `point` to a light `limit` away in `direction`,
+        /// with a penumbra where the ray only just misses something
+        pub fn soft_shadow(&self, sdf: &Sdf, point: Vec3, direction: Vec3, limit: f32) -> f32 {
+            if self.shadow_sharpness <= 0.0 {
+                return 1.0;
+            }
+            let mut visibility: f32 = 1.0;
+            let mut travelled = self.epsilon * 10.0;
+            for _ in 0..self.max_steps {
+                if travelled >= limit.min(self.max_distance) {
+                    break;
+                }
+                let distance = sdf.distance(point + direction * travelled);
+                if distance < self.epsilon {
+                    return 0.0;
+                }
+                visibility = visibility.min(self.shadow_sharpness * distance / travelled);
+                travelled += distance;
+            }
+            visibility.clamp(0.0, 1.0)
+        }
+
+        /// How open the surroundings of a surface point are, from 0 in a tight crease to 1,
+        /// found by checking how close the surface is at a few steps along the normal
+        pub fn ambient_occlusion(&self, sdf: &Sdf, point: Vec3, normal: Vec3) -> f32 {
+            let mut occlusion = 0.0;
+            let mut weight = 1.0;
+            for i in 1..=5 {
+                let step = 0.05 * i as f32;
+                occlusion += (step - sdf.distance(point + normal * step)).max(0.0) * weight;
+                weight *= 0.5;
+            }
+            (1.0 - self.occlusion * 3.0 * occlusion).clamp(0.0, 1.0)
+        }
+
+        /// The lit colour and glyph brightness at a point on the surface, seen from `eye`
+        fn shade(&self, sdf: &Sdf, point: Vec3, eye: Vec3) -> (Colour, f32) {
+            let normal = sdf.normal(point);
+            let material = sdf.sample(point).1;
+            // start just off the surface, so the surface doesn't shadow itself
+            let surface = point + normal * (self.epsilon * 2.0);
+            let lighting = illuminate_with(
+                &self.lights,
+                point,
+                normal,
+                (eye - point).normalized(),
+                material.shininess,
+                |i| {
+                    let light = &self.lights[i];
+                    match light.incident(point) {
+                        Some((direction, _)) => {
+                            self.soft_shadow(sdf, surface, direction, light.distance(point))
+                        }
+                        None => self.ambient_occlusion(sdf, point, normal),
+                    }
+                },
+            );
+            let extra = material.specular * lighting.specular + material.emissive;
+            (
+                material.diffuse * lighting.diffuse + extra,
+                lighting.diffuse.max_component() + extra.max_component(),
+            )
+        }
+
+        /// Draws the shape through the renderer, using the framebuffer's depth
+        /// so that it overlaps correctly with meshes
+        pub fn draw(
+            &self,
+            renderer: &Renderer,
+            framebuffer: &mut Framebuffer,
+            camera: &Camera,
+            sdf: &Sdf,
+        ) {
+            let (width, height) = (framebuffer.width(), framebuffer.height());
+            let forward = camera.forward();
+            for y in 0..height {
+                for x in 0..width {
+                    let ray = camera.ray(x as f32 + 0.5, y as f32 + 0.5, width, height);
+                    let Some(distance) = self.march(sdf, ray.origin, ray.direction) else {
+                        continue;
+                    };
+                    // the depth buffer holds distances along the camera's axis
+                    let depth = distance * ray.direction.dot(&forward);
+                    if depth < camera.near() || !framebuffer.test_depth(x, y, depth) {
+                        continue;
+                    }
+                    let point = ray.at(distance);
+                    let (colour, brightness) = self.shade(sdf, point, ray.origin);
+                    let ramp = sdf.sample(point).1.ramp.as_deref();
+                    renderer.plot(framebuffer, x, y, brightness, colour, ramp);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+pub mod shadow {
+    use crate::{
+        light::light::{Light, LightKind},
+        matrix::matrix::{Matrix, Vec3},
+        render::render::{rasterize_triangle, DepthBuffer},
+        shapes::shapes::{BoundingSphere, Mesh},
+    };
+
+    /// How shadows are drawn
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct ShadowSettings {
+        /// Width and height of each shadow map, in texels
+        pub resolution: usize,
+        /// How far a surface must be behind the closest one to the light to be shadowed.
+        /// Too little makes surfaces shadow themselves, and too much detaches shadows.
+        pub bias: f32,
+        /// Percentage-closer filtering averages a square of texels this far from the centre,
+        /// softening the edges of shadows. 0 gives hard edges.
+        pub softness: usize,
+    }
+
+    impl Default for ShadowSettings {
+        fn default() -> Self {
+            Self {
+                resolution: 256,
+                bias: 0.15,
+                softness: 1,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Projection {
+        /// For directional lights, covering a square `half_size` across either side of the centre
+        Orthographic { half_size: f32 },
+        /// For spot lights, covering a cone `half_angle` from the middle
+        Perspective { half_angle: f32 },
+    }
+
+    /// The distance from a light to the closest surface in each direction it shines
+    #[derive(Clone, Debug)]
+    pub struct ShadowMap {
+        /// Converts world coordinates to the light's view coordinates,
+        /// where the light shines down the negative z axis
+        view: Matrix<4, 4>,
+        projection: Projection,
+        depth: DepthBuffer,
+    }
+
+    impl ShadowMap {
+        /// The closest distance to the light which perspective shadow maps can record
+        const NEAR: f32 = 0.05;
+
+        /// An empty shadow map for a directional or spot light, covering everything in `bounds`.
+        /// Returns `None` for other lights, which don't cast shadows.
+        pub fn new(light: &Light, bounds: &BoundingSphere, resolution: usize) -> Option<Self> {
+            let (view, projection) = match light.kind {
+                LightKind::Directional { direction } => {
+                    let eye = bounds.centre - direction * (bounds.radius * 2.0);
+                    let projection = Projection::Orthographic {
+                        half_size: bounds.radius,
+                    };
+                    (look_at(eye, direction), projection)
+                }
+                LightKind::Spot {
+                    position,
+                    direction,
+                    outer_angle,
+                    ..
+                } => {
+                    let projection = Projection::Perspective {
+                        half_angle: outer_angle.min(1.5),
+                    };
+                    (look_at(position, direction), projection)
+                }
+                LightKind::Ambient | LightKind::Point { .. } => return None,
+            };
+            Some(Self {
+                view,
+                projection,
+                depth: DepthBuffer::new(resolution, resolution),
+            })
+        }
+
+        pub fn depth(&self) -> &DepthBuffer {
+            &self.depth
+        }
+
+        /// Records the mesh's distance from the light, as if it were attached to something
+        /// with the world matrix `parent`
+        pub fn add_mesh(&mut self, mesh: &Mesh, parent: &Matrix<4, 4>) {
+            let matrix = self.view * *parent * mesh.transformation().to_matrix();
+            let size = self.depth.width();
+            for triangle in mesh.posed_triangles() {
+                let points =
+                    triangle.map(|x| self.project_view(matrix.transform_point(x.position())));
+                let Some(points) = points.into_iter().collect::<Option<Vec<_>>>() else {
+                    continue;
+                };
+                let depths = [points[0].z(), points[1].z(), points[2].z()];
+                match self.projection {
+                    Projection::Perspective { .. } => {
+                        let points = [points[0], points[1], points[2]];
+                        rasterize_triangle(points, size, size, |x, y, depth, _| {
+                            self.depth.test_and_set(x, y, depth);
+                        });
+                    }
+                    Projection::Orthographic { .. } => {
+                        // depth is linear across the map, so the rasterizer mustn't correct it
+                        let points = [0, 1, 2].map(|i| matrix![points[i].x(); points[i].y(); 1]);
+                        rasterize_triangle(points, size, size, |x, y, _, weights| {
+                            let depth = (0..3).map(|i| depths[i] * weights[i]).sum();
+                            self.depth.test_and_set(x, y, depth);
+                        });
+                    }
+                }
+            }
+        }
+
+        /// How much light reaches `point`, from 0 for fully shadowed to 1 for fully lit.
+        /// Points outside the map are lit.
+        pub fn visibility(&self, point: Vec3, settings: &ShadowSettings) -> f32 {
+            let Some(projected) = self.project_view(self.view.transform_point(point)) else {
+                return 1.0;
+            };
+            let size = self.depth.width() as i64;
+            let (centre_x, centre_y) = (projected.x().floor() as i64, projected.y().floor() as i64);
+            let radius = settings.softness as i64;
+            let (mut lit, mut total) = (0, 0);
+            for y in centre_y - radius..=centre_y + radius {
+                for x in centre_x - radius..=centre_x + radius {
+                    total += 1;
+                    if x < 0
+                        || y < 0
+                        || x >= size
+                        || y >= size
+                        || projected.z() - settings.bias <= self.depth.get(x as usize, y as usize)
+                    {
+                        lit += 1;
+                    }
+                }
+            }
+            lit as f32 / total as f32
+        }
+
+        /// The texel column, row and distance from the light of a point in the light's
+        /// view coordinates, or `None` if it's behind a spot light
+        fn project_view(&self, view: Vec3) -> Option<Vec3> {
+            let size = self.depth.width() as f32;
+            let depth = -view.z();
+            let half_size = match self.projection {
+                Projection::Orthographic { half_size } => half_size,
+                Projection::Perspective { half_angle } => {
+                    if depth < Self::NEAR {
+                        return None;
+                    }
+                    half_angle.tan() * depth
+                }
+            };
+            Some(matrix![
+                (view.x() / half_size + 1.0) * 0.5 * size;
+                (1.0 - view.y() / half_size) * 0.5 * size;
+                depth
+            ])
+        }
+    }
+
+    /// A view matrix for looking from `eye` along `direction`
+    fn look_at(eye: Vec3, direction: Vec3) -> Matrix<4, 4> {
+        let forward = direction.normalized();
+        // any up vector will do, as long as it isn't parallel to the direction
+        let up = if forward.y().abs() > 0.99 {
+            matrix![0; 0; 1]
+        } else {
+            matrix![0; 1; 0]
+        };
+        let right = forward.cross(&up).normalized();
+        let up = right.cross(&forward);
+        matrix! {
+            right.x(), right.y(), right.z(), -right.dot(&eye);
+            up.x(), up.y(), up.z(), -up.dot(&eye);
+            -forward.x(), -forward.y(), -forward.z(), forward.dot(&eye);
+            0, 0, 0, 1
+        }
+    }
+}
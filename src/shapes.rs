@@ -1,39 +1,169 @@
 pub mod shapes {
-    use ruscii::drawing::Pencil;
+    use std::{
+        f32::consts::PI,
+        ops::{Mul, Range},
+    };
 
-    use crate::matrix::matrix::{Matrix, Vec2, Vec3};
+    use crate::{
+        camera::camera::Camera,
+        colour::colour::Colour,
+        material::material::Material,
+        matrix::matrix::{Matrix, Vec2, Vec3},
+        render::render::Renderer,
+        screen::screen::Framebuffer,
+        skeleton::skeleton::Skeleton,
+    };
 
     #[derive(Clone, Copy, Debug)]
     pub struct Vertex {
         position: Vec3,
+        /// If there's no normal, the triangle's normal is used instead
+        normal: Option<Vec3>,
+        colour: Colour,
+        /// Texture coordinates, where `(0, 0)` is the bottom left of the texture
+        uv: Vec2,
+        /// Indices of the skeleton joints which move this vertex
+        joints: [usize; 4],
+        /// How much each of `joints` affects this vertex. These should add up to 1,
+        /// or all be 0 if the vertex isn't attached to the skeleton.
+        weights: [f32; 4],
+    }
+
+    impl Vertex {
+        pub fn new(position: Vec3) -> Self {
+            Self {
+                position,
+                normal: None,
+                colour: Colour::WHITE,
+                uv: matrix![0; 0],
+                joints: [0; 4],
+                weights: [0.0; 4],
+            }
+        }
+
+        pub fn with_position(self, position: Vec3) -> Self {
+            Self { position, ..self }
+        }
+
+        pub fn with_normal(self, normal: Option<Vec3>) -> Self {
+            Self { normal, ..self }
+        }
+
+        pub fn with_colour(self, colour: Colour) -> Self {
+            Self { colour, ..self }
+        }
+
+        pub fn with_uv(self, uv: Vec2) -> Self {
+            Self { uv, ..self }
+        }
+
+        pub fn with_joints(self, joints: [usize; 4], weights: [f32; 4]) -> Self {
+            Self {
+                joints,
+                weights,
+                ..self
+            }
+        }
+
+        pub fn position(&self) -> Vec3 {
+            self.position
+        }
+
+        pub fn normal(&self) -> Option<Vec3> {
+            self.normal
+        }
+
+        pub fn colour(&self) -> Colour {
+            self.colour
+        }
+
+        pub fn uv(&self) -> Vec2 {
+            self.uv
+        }
+
+        pub fn joints(&self) -> [usize; 4] {
+            self.joints
+        }
+
+        pub fn weights(&self) -> [f32; 4] {
+            self.weights
+        }
     }
 
     impl From<Vec3> for Vertex {
         fn from(value: Vec3) -> Self {
-            Self { position: value }
+            Self::new(value)
         }
     }
 
     pub struct Triangle {
         points: [Vertex; 3],
+        /// Index into the mesh's materials
+        material: usize,
     }
 
     impl Triangle {
         pub fn new(p1: Vec3, p2: Vec3, p3: Vec3) -> Self {
+            Self::from_vertices([p1.into(), p2.into(), p3.into()])
+        }
+
+        pub fn from_vertices(points: [Vertex; 3]) -> Self {
             Self {
-                points: [p1.into(), p2.into(), p3.into()],
+                points,
+                material: 0,
             }
         }
 
+        pub fn with_material(self, material: usize) -> Self {
+            Self { material, ..self }
+        }
+
+        pub fn vertices(&self) -> &[Vertex; 3] {
+            &self.points
+        }
+
+        pub fn material(&self) -> usize {
+            self.material
+        }
+
         pub fn normal(&self) -> Vec3 {
             (self.points[1].position - self.points[0].position)
                 .cross(&(self.points[2].position - self.points[0].position))
         }
     }
 
+    /// Offsets to each vertex, in the same order as the mesh's triangles' corners
+    #[derive(Clone, Debug)]
+    pub struct MorphTarget {
+        name: String,
+        positions: Vec<Vec3>,
+        /// Empty if the target doesn't change normals
+        normals: Vec<Vec3>,
+    }
+
+    impl MorphTarget {
+        pub fn new(name: &str, positions: Vec<Vec3>, normals: Vec<Vec3>) -> Self {
+            Self {
+                name: name.to_string(),
+                positions,
+                normals,
+            }
+        }
+
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
     pub struct Mesh {
         triangles: Vec<Triangle>,
         transformation: Transformation,
+        /// Never empty. The first is used by triangles which haven't been given another.
+        materials: Vec<Material>,
+        skeleton: Option<Skeleton>,
+        morph_targets: Vec<MorphTarget>,
+        /// How much of each morph target to add, in the same order as `morph_targets`
+        morph_weights: Vec<f32>,
     }
 
     impl Mesh {
@@ -41,36 +171,405 @@ pub mod shapes {
             Self {
                 triangles,
                 transformation: Default::default(),
+                materials: vec![Material::default()],
+                skeleton: None,
+                morph_targets: Vec::new(),
+                morph_weights: Vec::new(),
             }
         }
 
-        pub fn draw(&self, pencil: &mut Pencil) {
-            let points: Vec<_> = self
-                .triangles
+        /// An axis-aligned cuboid centred on the origin, with outward-facing triangles
+        pub fn cuboid(size: Vec3) -> Self {
+            let [x, y, z] = (size * 0.5).as_slice();
+            let corner = |i: usize| {
+                matrix![
+                    if i & 1 == 0 { -x } else { x };
+                    if i & 2 == 0 { -y } else { y };
+                    if i & 4 == 0 { -z } else { z }
+                ]
+            };
+            // each face as corner indices, counter-clockwise when seen from outside
+            let faces = [
+                [0, 2, 3, 1],
+                [4, 5, 7, 6],
+                [0, 1, 5, 4],
+                [2, 6, 7, 3],
+                [0, 4, 6, 2],
+                [1, 3, 7, 5],
+            ];
+            let triangles = faces
                 .iter()
-                .map(|x| x.points)
-                .flat_map(|x| x.map(|x| x.position))
-                .map(|x| self.transformation.transform(x))
-                .map(|x| matrix![x.x(); x.y()])
-                .map(|x| ruscii::spatial::Vec2 {
-                    x: x.x() as i32,
-                    y: x.y() as i32,
+                .flat_map(|&[a, b, c, d]| {
+                    [
+                        Triangle::new(corner(a), corner(b), corner(c)),
+                        Triangle::new(corner(a), corner(c), corner(d)),
+                    ]
                 })
-                .collect::<Vec<ruscii::spatial::Vec2>>();
-            let triangles: Vec<_> = points.chunks_exact(3).collect();
-            for triangle in triangles {
-                pencil
-                    .draw_char('#', triangle[0])
-                    .draw_char('#', triangle[1])
-                    .draw_char('#', triangle[2]);
+                .collect();
+            Self::new(triangles)
+        }
+
+        /// A sphere centred on the origin, made of `slices` segments around the y axis
+        /// and `stacks` segments from pole to pole
+        pub fn uv_sphere(radius: f32, slices: usize, stacks: usize) -> Self {
+            let point = |slice: usize, stack: usize| {
+                let theta = 2.0 * PI * slice as f32 / slices as f32;
+                let phi = PI * stack as f32 / stacks as f32;
+                matrix![
+                    radius * phi.sin() * theta.cos();
+                    radius * phi.cos();
+                    -radius * phi.sin() * theta.sin()
+                ]
+            };
+            let mut triangles = Vec::new();
+            for stack in 0..stacks {
+                for slice in 0..slices {
+                    let top_left = point(slice, stack);
+                    let top_right = point(slice + 1, stack);
+                    let bottom_left = point(slice, stack + 1);
+                    let bottom_right = point(slice + 1, stack + 1);
+                    if stack != 0 {
+                        triangles.push(Triangle::new(top_left, bottom_left, top_right));
+                    }
+                    if stack != stacks - 1 {
+                        triangles.push(Triangle::new(top_right, bottom_left, bottom_right));
+                    }
+                }
+            }
+            Self::new(triangles)
+        }
+
+        pub fn triangles(&self) -> &[Triangle] {
+            &self.triangles
+        }
+
+        /// The default material, used by triangles which haven't been given another
+        pub fn material(&self) -> &Material {
+            &self.materials[0]
+        }
+
+        pub fn set_material(&mut self, material: Material) {
+            self.materials[0] = material;
+        }
+
+        pub fn materials(&self) -> &[Material] {
+            &self.materials
+        }
+
+        pub fn materials_mut(&mut self) -> &mut [Material] {
+            &mut self.materials
+        }
+
+        /// Adds a material which triangles can be assigned, returning its index
+        pub fn add_material(&mut self, material: Material) -> usize {
+            self.materials.push(material);
+            self.materials.len() - 1
+        }
+
+        /// Gives a range of triangles the material at index `material`.
+        /// Fails if either is out of bounds, or the range is backwards.
+        #[allow(clippy::result_unit_err)]
+        pub fn assign_material(
+            &mut self,
+            triangles: Range<usize>,
+            material: usize,
+        ) -> Result<(), ()> {
+            if material >= self.materials.len()
+                || triangles.start > triangles.end
+                || triangles.end > self.triangles.len()
+            {
+                return Err(());
+            }
+            for triangle in &mut self.triangles[triangles] {
+                triangle.material = material;
+            }
+            Ok(())
+        }
+
+        /// The material the triangle at `index` is drawn with,
+        /// falling back to the default if its material doesn't exist
+        pub fn triangle_material(&self, index: usize) -> &Material {
+            self.triangles
+                .get(index)
+                .and_then(|x| self.materials.get(x.material))
+                .unwrap_or(&self.materials[0])
+        }
+
+        pub fn skeleton(&self) -> Option<&Skeleton> {
+            self.skeleton.as_ref()
+        }
+
+        pub fn skeleton_mut(&mut self) -> Option<&mut Skeleton> {
+            self.skeleton.as_mut()
+        }
+
+        /// Attaches a skeleton, which deforms the mesh according to each vertex's joints
+        pub fn set_skeleton(&mut self, skeleton: Option<Skeleton>) {
+            self.skeleton = skeleton;
+        }
+
+        /// Adds a morph target with a weight of 0, returning its index.
+        /// Fails unless the target has an offset for every corner of every triangle.
+        #[allow(clippy::result_unit_err)]
+        pub fn add_morph_target(&mut self, target: MorphTarget) -> Result<usize, ()> {
+            let corners = self.triangles.len() * 3;
+            if target.positions.len() != corners
+                || !(target.normals.is_empty() || target.normals.len() == corners)
+            {
+                return Err(());
+            }
+            self.morph_targets.push(target);
+            self.morph_weights.push(0.0);
+            Ok(self.morph_targets.len() - 1)
+        }
+
+        pub fn morph_targets(&self) -> &[MorphTarget] {
+            &self.morph_targets
+        }
+
+        pub fn morph_weights(&self) -> &[f32] {
+            &self.morph_weights
+        }
+
+        pub fn morph_weights_mut(&mut self) -> &mut [f32] {
+            &mut self.morph_weights
+        }
+
+        pub fn transformation(&self) -> &Transformation {
+            &self.transformation
+        }
+
+        pub fn set_transformation(&mut self, transformation: Transformation) {
+            self.transformation = transformation;
+        }
+
+        /// The smallest axis-aligned box containing the mesh, ignoring its transformation
+        pub fn local_bounding_box(&self) -> Option<BoundingBox> {
+            BoundingBox::from_points(self.local_triangles().into_iter().flatten())
+        }
+
+        /// The smallest axis-aligned box containing the mesh after its transformation
+        pub fn bounding_box(&self) -> Option<BoundingBox> {
+            BoundingBox::from_points(self.transformed_triangles().flatten())
+        }
+
+        /// A sphere containing the mesh, ignoring its transformation
+        pub fn local_bounding_sphere(&self) -> Option<BoundingSphere> {
+            BoundingSphere::from_points(self.local_triangles().into_iter().flatten())
+        }
+
+        /// A sphere containing the mesh after its transformation
+        pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+            BoundingSphere::from_points(self.transformed_triangles().flatten())
+        }
+
+        /// Every triangle's vertices after blending morph targets and then skinning,
+        /// but before the mesh's transformation
+        pub fn posed_triangles(&self) -> Vec<[Vertex; 3]> {
+            let mut triangles: Vec<_> = self.triangles.iter().map(|x| x.points).collect();
+            let morphs = self
+                .morph_targets
+                .iter()
+                .zip(self.morph_weights.iter())
+                .filter(|(_, &weight)| weight != 0.0);
+            for (target, &weight) in morphs {
+                for (i, vertex) in triangles.iter_mut().flatten().enumerate() {
+                    vertex.position = vertex.position + target.positions[i] * weight;
+                    if let (Some(normal), Some(&offset)) = (vertex.normal, target.normals.get(i)) {
+                        vertex.normal = Some(normal + offset * weight);
+                    }
+                }
             }
+            if let Some(skeleton) = &self.skeleton {
+                let matrices = skeleton.skinning_matrices();
+                for vertex in triangles.iter_mut().flatten() {
+                    *vertex = Skeleton::skin(&matrices, vertex);
+                }
+            }
+            triangles
+        }
+
+        /// The corners of every triangle after morphing and skinning,
+        /// but before the mesh's transformation
+        fn local_triangles(&self) -> Vec<[Vec3; 3]> {
+            self.posed_triangles()
+                .into_iter()
+                .map(|x| x.map(|x| x.position))
+                .collect()
         }
+
+        /// The corners of every triangle, after applying the mesh's transformation
+        fn transformed_triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+            self.local_triangles()
+                .into_iter()
+                .map(|x| x.map(|x| self.transformation.transform(x)))
+        }
+
+        pub fn surface_area(&self) -> f32 {
+            self.transformed_triangles()
+                .map(|[a, b, c]| (b - a).cross(&(c - a)).length() * 0.5)
+                .sum()
+        }
+
+        /// The signed volume enclosed by the mesh.
+        /// This is only meaningful for closed meshes, and is positive
+        /// when the triangles wind counter-clockwise seen from outside.
+        pub fn volume(&self) -> f32 {
+            self.transformed_triangles()
+                .map(|[a, b, c]| a.dot(&b.cross(&c)) / 6.0)
+                .sum()
+        }
+
+        /// The centre of mass of the enclosed volume, assuming uniform density,
+        /// or `None` if the mesh encloses no volume, such as when it's empty or flat
+        pub fn centre_of_mass(&self) -> Option<Vec3> {
+            let (volume, size, moment) = self.transformed_triangles().fold(
+                (0.0, 0.0, matrix![0; 0; 0]),
+                |(volume, size, moment), [a, b, c]| {
+                    let tetrahedron = a.dot(&b.cross(&c)) / 6.0;
+                    (
+                        volume + tetrahedron,
+                        size + tetrahedron.abs(),
+                        moment + (a + b + c) * (tetrahedron / 4.0),
+                    )
+                },
+            );
+            encloses_volume(volume, size).then(|| moment / volume)
+        }
+
+        /// The inertia tensor about the centre of mass, assuming a uniform density of 1,
+        /// so that the mass equals the volume. Like `centre_of_mass`, this is `None`
+        /// if the mesh encloses no volume.
+        pub fn inertia_tensor(&self) -> Option<Matrix<3, 3>> {
+            // Each triangle forms a tetrahedron with the origin. Their covariance matrices
+            // (the integral of r r^T over the volume) can be summed, shifted to the centre
+            // of mass, and converted to an inertia tensor with I = tr(C) * Id - C.
+            let canonical = matrix! {
+                2, 1, 1;
+                1, 2, 1;
+                1, 1, 2
+            };
+            let (volume, size, moment, covariance) = self.transformed_triangles().fold(
+                (
+                    0.0,
+                    0.0,
+                    matrix![0; 0; 0],
+                    Matrix::<3, 3>::new([[0.0; 3]; 3]),
+                ),
+                |(volume, size, moment, covariance), [a, b, c]| {
+                    let corners = matrix! {
+                        a.x(), b.x(), c.x();
+                        a.y(), b.y(), c.y();
+                        a.z(), b.z(), c.z()
+                    };
+                    let determinant = corners.determinant();
+                    let tetrahedron = determinant / 6.0;
+                    (
+                        volume + tetrahedron,
+                        size + tetrahedron.abs(),
+                        moment + (a + b + c) * (tetrahedron / 4.0),
+                        covariance
+                            + corners * canonical * corners.transpose() * (determinant / 120.0),
+                    )
+                },
+            );
+            if !encloses_volume(volume, size) {
+                return None;
+            }
+            let centre = moment / volume;
+            let covariance = covariance - centre.outer(&centre) * volume;
+            Some(Matrix::identity() * covariance.trace() - covariance)
+        }
+
+        pub fn draw(
+            &self,
+            renderer: &mut Renderer,
+            framebuffer: &mut Framebuffer,
+            camera: &Camera,
+        ) {
+            renderer.draw_mesh(framebuffer, camera, self, &Matrix::identity());
+        }
+    }
+
+    /// Whether the signed volume of a mesh is more than rounding error, given the
+    /// total size of the tetrahedra it was summed from, which cancel out for flat meshes
+    fn encloses_volume(volume: f32, size: f32) -> bool {
+        volume.abs() > size * 1e-5
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct BoundingBox {
+        pub min: Vec3,
+        pub max: Vec3,
+    }
+
+    impl BoundingBox {
+        /// Returns `None` if there are no points
+        pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+            points.into_iter().fold(None, |bounds, point| {
+                Some(match bounds {
+                    None => Self {
+                        min: point,
+                        max: point,
+                    },
+                    Some(Self { min, max }) => Self {
+                        min: matrix![
+                            min.x().min(point.x());
+                            min.y().min(point.y());
+                            min.z().min(point.z())
+                        ],
+                        max: matrix![
+                            max.x().max(point.x());
+                            max.y().max(point.y());
+                            max.z().max(point.z())
+                        ],
+                    },
+                })
+            })
+        }
+
+        pub fn centre(&self) -> Vec3 {
+            (self.min + self.max) * 0.5
+        }
+
+        pub fn size(&self) -> Vec3 {
+            self.max - self.min
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct BoundingSphere {
+        pub centre: Vec3,
+        pub radius: f32,
     }
 
+    impl BoundingSphere {
+        /// A sphere around the centre of the points' bounding box.
+        /// This isn't the smallest possible sphere, but it's never far off.
+        /// Returns `None` if there are no points
+        pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+            let points: Vec<_> = points.into_iter().collect();
+            let centre = BoundingBox::from_points(points.iter().copied())?.centre();
+            let radius = points
+                .iter()
+                .map(|&x| (x - centre).length())
+                .fold(0.0, f32::max);
+            Some(Self { centre, radius })
+        }
+    }
+
+    /// Scales and then rotates points about `pivot`, and then moves them by `translation`.
+    /// Rotations are Euler angles in radians, applied about the x, then y, then z axes.
+    ///
+    /// Transformations can't hold shear, so combining or undoing them doesn't always give
+    /// another transformation. Their matrices, from `to_matrix`, can always be combined and
+    /// undone, so multiplying transformations gives a matrix, and `inverse` can be `None`.
+    #[derive(Clone, Copy, Debug, PartialEq)]
     pub struct Transformation {
-        translation: Vec3,
-        rotation: Vec3,
-        scale: Vec3,
+        pub translation: Vec3,
+        pub rotation: Vec3,
+        pub scale: Vec3,
+        pub pivot: Vec3,
     }
 
     impl Transformation {
@@ -79,39 +578,139 @@ pub mod shapes {
                 translation,
                 rotation,
                 scale,
+                pivot: matrix![0; 0; 0],
             }
         }
 
         pub fn translation(translation: Vec3) -> Self {
             Self {
                 translation,
-                rotation: matrix![0; 0; 0],
-                scale: matrix![1; 1; 1],
+                ..Default::default()
             }
         }
 
         pub fn rotation(rotation: Vec3) -> Self {
             Self {
-                translation: matrix![0; 0; 0],
                 rotation,
-                scale: matrix![1; 1; 1],
+                ..Default::default()
             }
         }
 
         pub fn scale(scale: Vec3) -> Self {
             Self {
-                translation: matrix![0; 0; 0],
-                rotation: matrix![0; 0; 0],
                 scale,
+                ..Default::default()
             }
         }
 
+        pub fn with_pivot(self, pivot: Vec3) -> Self {
+            Self { pivot, ..self }
+        }
+
         pub fn transform(&self, point: Vec3) -> Vec3 {
-            (Transformation::from_rotation(self.rotation) * (point - self.translation))
-                .component_mult(&self.scale)
+            self.rotation_matrix() * (point - self.pivot).component_mult(&self.scale)
+                + self.pivot
                 + self.translation
         }
 
+        /// Undoes `transform`. This is exact even when `inverse` can't be.
+        pub fn inverse_transform(&self, point: Vec3) -> Vec3 {
+            let unscale = matrix![1.0 / self.scale.x(); 1.0 / self.scale.y(); 1.0 / self.scale.z()];
+            (self.rotation_matrix().transpose() * (point - self.translation - self.pivot))
+                .component_mult(&unscale)
+                + self.pivot
+        }
+
+        pub fn rotation_matrix(&self) -> Matrix<3, 3> {
+            Transformation::from_rotation(self.rotation)
+        }
+
+        pub fn to_matrix(self) -> Matrix<4, 4> {
+            let scale = matrix! {
+                self.scale.x(), 0, 0;
+                0, self.scale.y(), 0;
+                0, 0, self.scale.z()
+            };
+            Matrix::from_translation(self.translation + self.pivot)
+                * Matrix::from_linear(self.rotation_matrix() * scale)
+                * Matrix::from_translation(-self.pivot)
+        }
+
+        /// Splits an affine matrix into translation, rotation and scale, with the pivot at the
+        /// origin. Shear can't be represented, so this is only exact for matrices built from
+        /// uniform scales, or from scales along the same axes as the rotation.
+        pub fn from_matrix(matrix: &Matrix<4, 4>) -> Self {
+            let linear = matrix.linear();
+            let columns = linear.transpose();
+            let column = |i: usize| matrix![columns[i][0]; columns[i][1]; columns[i][2]];
+            let mut scale = matrix![column(0).length(); column(1).length(); column(2).length()];
+            if linear.determinant() < 0.0 {
+                scale.set_x(-scale.x());
+            }
+            let mut rotation = linear;
+            for i in 0..3 {
+                for j in 0..3 {
+                    rotation[i][j] /= scale[j][0];
+                }
+            }
+
+            Self {
+                translation: matrix![matrix[0][3]; matrix[1][3]; matrix[2][3]],
+                rotation: Transformation::euler_angles(&rotation),
+                scale,
+                pivot: matrix![0; 0; 0],
+            }
+        }
+
+        /// The transformation which undoes this one. This is `None` if a scale is 0, or if undoing
+        /// it would need shear, which happens when a non-uniform scale is followed by a rotation.
+        /// `inverse_transform` and `to_matrix().inverse()` work either way.
+        pub fn inverse(&self) -> Option<Self> {
+            let matrix = self.to_matrix().inverse()?;
+            let inverse = Transformation::from_matrix(&matrix);
+            let error = (inverse.to_matrix() - matrix)
+                .values()
+                .iter()
+                .flatten()
+                .fold(0.0f32, |x, y| x.max(y.abs()));
+            let size = matrix
+                .values()
+                .iter()
+                .flatten()
+                .fold(1.0f32, |x, y| x.max(y.abs()));
+            (error <= size * 1e-4).then_some(inverse)
+        }
+
+        /// Interpolates each part separately, going from `self` at `t = 0` to `other` at `t = 1`.
+        /// Rotations take the direct route between the two sets of angles.
+        pub fn lerp(&self, other: &Transformation, t: f32) -> Self {
+            let lerp = |a: Vec3, b: Vec3| a + (b - a) * t;
+            Self {
+                translation: lerp(self.translation, other.translation),
+                rotation: lerp(self.rotation, other.rotation),
+                scale: lerp(self.scale, other.scale),
+                pivot: lerp(self.pivot, other.pivot),
+            }
+        }
+
+        /// Recovers the angles used by `from_rotation`
+        fn euler_angles(rotation: &Matrix<3, 3>) -> Vec3 {
+            let sin_y = (-rotation[2][0]).clamp(-1.0, 1.0);
+            let y = sin_y.asin();
+            if sin_y.abs() < 1.0 - 1e-6 {
+                matrix![
+                    rotation[2][1].atan2(rotation[2][2]);
+                    y;
+                    rotation[1][0].atan2(rotation[0][0])
+                ]
+            } else if sin_y > 0.0 {
+                // gimbal lock: only x - z is known, so put it all in x
+                matrix![rotation[0][1].atan2(rotation[1][1]); y; 0]
+            } else {
+                matrix![(-rotation[0][1]).atan2(rotation[1][1]); y; 0]
+            }
+        }
+
         fn from_rotation(rotation: Vec3) -> Matrix<3, 3> {
             let [x, y, z] = [rotation.x(), rotation.y(), rotation.z()];
 
@@ -148,7 +747,36 @@ pub mod shapes {
                 translation: matrix![0; 0; 0],
                 rotation: matrix![0; 0; 0],
                 scale: matrix![1; 1; 1],
+                pivot: matrix![0; 0; 0],
             }
         }
     }
+
+    /// `(a * b).transform_point(x)` is `a.transform(b.transform(x))`. This is a matrix, since
+    /// combining a non-uniform scale with a rotation can need shear, which a `Transformation`
+    /// can't hold. `Transformation::from_matrix` turns it back into one when it can.
+    impl Mul<Transformation> for Transformation {
+        type Output = Matrix<4, 4>;
+
+        fn mul(self, rhs: Transformation) -> Self::Output {
+            self.to_matrix() * rhs.to_matrix()
+        }
+    }
+
+    /// Applies `rhs` first, so that combined transformations can be combined again
+    impl Mul<Transformation> for Matrix<4, 4> {
+        type Output = Matrix<4, 4>;
+
+        fn mul(self, rhs: Transformation) -> Self::Output {
+            self * rhs.to_matrix()
+        }
+    }
+
+    impl Mul<Matrix<4, 4>> for Transformation {
+        type Output = Matrix<4, 4>;
+
+        fn mul(self, rhs: Matrix<4, 4>) -> Self::Output {
+            self.to_matrix() * rhs
+        }
+    }
 }
@@ -0,0 +1,151 @@
+pub mod skeleton {
+    use crate::{
+        animation::animation::AnimationTarget,
+        matrix::matrix::Matrix,
+        shapes::shapes::{Transformation, Vertex},
+    };
+
+    #[derive(Clone, Debug)]
+    pub struct Joint {
+        name: String,
+        parent: Option<usize>,
+        /// Relative to the parent joint, or to the mesh for the root joint
+        pub transformation: Transformation,
+        /// Converts mesh coordinates into coordinates local to the joint in its bind pose
+        inverse_bind: Matrix<4, 4>,
+    }
+
+    impl Joint {
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        pub fn parent(&self) -> Option<usize> {
+            self.parent
+        }
+
+        pub fn inverse_bind(&self) -> &Matrix<4, 4> {
+            &self.inverse_bind
+        }
+    }
+
+    /// A hierarchy of joints. Parents always come before their children,
+    /// so joint indices stay valid for the skeleton's lifetime.
+    #[derive(Clone, Debug, Default)]
+    pub struct Skeleton {
+        joints: Vec<Joint>,
+    }
+
+    impl Skeleton {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Adds a joint, returning its index. The inverse bind matrix starts as the identity,
+        /// so call `bind` or `set_inverse_bind` once the skeleton is in its rest pose.
+        /// Fails if `parent` isn't in the skeleton.
+        #[allow(clippy::result_unit_err)]
+        pub fn add_joint(
+            &mut self,
+            name: &str,
+            parent: Option<usize>,
+            transformation: Transformation,
+        ) -> Result<usize, ()> {
+            if parent.is_some_and(|x| x >= self.joints.len()) {
+                return Err(());
+            }
+            self.joints.push(Joint {
+                name: name.to_string(),
+                parent,
+                transformation,
+                inverse_bind: Matrix::identity(),
+            });
+            Ok(self.joints.len() - 1)
+        }
+
+        pub fn joints(&self) -> &[Joint] {
+            &self.joints
+        }
+
+        pub fn joint_mut(&mut self, index: usize) -> Option<&mut Joint> {
+            self.joints.get_mut(index)
+        }
+
+        pub fn find(&self, name: &str) -> Option<usize> {
+            self.joints.iter().position(|x| x.name == name)
+        }
+
+        /// Sets inverse bind matrices, such as those loaded from a model file
+        pub fn set_inverse_bind(&mut self, index: usize, inverse_bind: Matrix<4, 4>) {
+            self.joints[index].inverse_bind = inverse_bind;
+        }
+
+        /// Makes the current pose the bind pose, so that it leaves the mesh undeformed
+        pub fn bind(&mut self) {
+            let worlds = self.world_matrices();
+            for (joint, world) in self.joints.iter_mut().zip(worlds) {
+                joint.inverse_bind = world.inverse().unwrap_or_else(Matrix::identity);
+            }
+        }
+
+        /// Converts coordinates local to each joint into mesh coordinates
+        pub fn world_matrices(&self) -> Vec<Matrix<4, 4>> {
+            let mut result: Vec<Matrix<4, 4>> = Vec::with_capacity(self.joints.len());
+            for joint in self.joints.iter() {
+                let local = joint.transformation.to_matrix();
+                let world = match joint.parent {
+                    Some(parent) => result[parent] * local,
+                    None => local,
+                };
+                result.push(world);
+            }
+            result
+        }
+
+        /// The matrix for each joint which moves a vertex from the bind pose to the current pose
+        pub fn skinning_matrices(&self) -> Vec<Matrix<4, 4>> {
+            self.world_matrices()
+                .into_iter()
+                .zip(self.joints.iter())
+                .map(|(world, joint)| world * joint.inverse_bind)
+                .collect()
+        }
+
+        /// Linear blend skinning: the weighted average of the vertex moved by each of its joints.
+        /// Vertices without any weights, or with joints outside the skeleton, don't move.
+        pub fn skin(skinning_matrices: &[Matrix<4, 4>], vertex: &Vertex) -> Vertex {
+            let influences = vertex
+                .joints()
+                .into_iter()
+                .zip(vertex.weights())
+                .filter(|&(_, weight)| weight != 0.0);
+            let mut total = 0.0;
+            let mut blended = Matrix::<4, 4>::new([[0.0; 4]; 4]);
+            for (joint, weight) in influences {
+                let Some(&matrix) = skinning_matrices.get(joint) else {
+                    return *vertex;
+                };
+                blended = blended + matrix * weight;
+                total += weight;
+            }
+            if total == 0.0 {
+                return *vertex;
+            }
+            let blended = blended / total;
+            vertex
+                .with_position(blended.transform_point(vertex.position()))
+                .with_normal(
+                    vertex
+                        .normal()
+                        .map(|x| blended.transform_vector(x).normalized()),
+                )
+        }
+    }
+
+    impl AnimationTarget for Skeleton {
+        fn target_mut(&mut self, name: &str) -> Option<&mut Transformation> {
+            let index = self.find(name)?;
+            Some(&mut self.joints[index].transformation)
+        }
+    }
+}
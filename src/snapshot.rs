@@ -0,0 +1,143 @@
+pub mod snapshot {
+    use std::{fmt::Write, fs, path::PathBuf};
+
+    use crate::screen::screen::Framebuffer;
+
+    /// Setting this environment variable to anything makes `check` overwrite the golden
+    /// files with the current output, instead of comparing against them
+    pub const BLESS_VARIABLE: &str = "BLESS_SNAPSHOTS";
+
+    /// How many differing cells a diff lists before giving up
+    const MAX_LISTED_CELLS: usize = 20;
+
+    /// Where the golden file for a snapshot is kept
+    pub fn golden_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("snapshots")
+            .join(format!("{name}.txt"))
+    }
+
+    /// Compares the framebuffer's glyphs with the golden file for `name`, returning a
+    /// readable description of the differences if there are any.
+    /// With `BLESS_SNAPSHOTS` set, the golden file is written instead.
+    pub fn check(name: &str, framebuffer: &Framebuffer) -> Result<(), String> {
+        check_within(name, framebuffer, "", 0)
+    }
+
+    /// Like `check`, but glyphs which are at most `steps` apart in `ramp` count as the same.
+    /// This is for output which floating point rounding can nudge, like the path tracer's.
+    pub fn check_within(
+        name: &str,
+        framebuffer: &Framebuffer,
+        ramp: &str,
+        steps: usize,
+    ) -> Result<(), String> {
+        let path = golden_path(name);
+        let actual = framebuffer.to_text();
+        if std::env::var_os(BLESS_VARIABLE).is_some() {
+            let write = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, &actual));
+            return write.map_err(|error| format!("couldn't bless {}: {error}", path.display()));
+        }
+        let Ok(expected) = fs::read_to_string(&path) else {
+            return Err(format!(
+                "snapshot `{name}` has no golden file at {}. \
+                 Run the tests with {BLESS_VARIABLE}=1 to create it.\n{actual}",
+                path.display()
+            ));
+        };
+        // goldens checked out on Windows may have gained carriage returns
+        let expected = expected.replace("\r\n", "\n");
+        match diff_within(&expected, &actual, ramp, steps) {
+            None => Ok(()),
+            Some(diff) => Err(format!(
+                "snapshot `{name}` doesn't match {}. \
+                 Run the tests with {BLESS_VARIABLE}=1 if the change is intended.\n{diff}",
+                path.display()
+            )),
+        }
+    }
+
+    /// The differences between two pieces of text cell by cell, or `None` if they're the same.
+    /// Rows which differ are shown one above the other with the differing cells marked,
+    /// followed by a list of the cells.
+    pub fn diff(expected: &str, actual: &str) -> Option<String> {
+        diff_within(expected, actual, "", 0)
+    }
+
+    /// Like `diff`, but glyphs which are at most `steps` apart in `ramp` count as the same
+    pub fn diff_within(expected: &str, actual: &str, ramp: &str, steps: usize) -> Option<String> {
+        if expected == actual {
+            return None;
+        }
+        let index = |x: char| ramp.chars().position(|y| y == x);
+        let same = |a: Option<&char>, b: Option<&char>| match (a, b) {
+            (Some(&a), Some(&b)) => {
+                a == b
+                    || matches!((index(a), index(b)), (Some(a), Some(b)) if a.abs_diff(b) <= steps)
+            }
+            _ => a == b,
+        };
+        let expected: Vec<Vec<char>> = expected.lines().map(|x| x.chars().collect()).collect();
+        let actual: Vec<Vec<char>> = actual.lines().map(|x| x.chars().collect()).collect();
+        let size = |rows: &[Vec<char>]| {
+            let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+            format!("{width}x{}", rows.len())
+        };
+
+        let mut output = String::new();
+        let (expected_size, actual_size) = (size(&expected), size(&actual));
+        if expected_size != actual_size {
+            writeln!(output, "expected {expected_size} cells, got {actual_size}").unwrap();
+        }
+
+        let mut cells = Vec::new();
+        let empty = Vec::new();
+        for y in 0..expected.len().max(actual.len()) {
+            let expected_row = expected.get(y).unwrap_or(&empty);
+            let actual_row = actual.get(y).unwrap_or(&empty);
+            let width = expected_row.len().max(actual_row.len());
+            let marker: String = (0..width)
+                .map(|x| {
+                    let (a, b) = (expected_row.get(x), actual_row.get(x));
+                    if same(a, b) {
+                        ' '
+                    } else {
+                        cells.push((x, y, a.copied(), b.copied()));
+                        '^'
+                    }
+                })
+                .collect();
+            if marker.trim().is_empty() {
+                continue;
+            }
+            let row: String = expected_row.iter().collect();
+            writeln!(output, "{y:>4} expected |{row}|").unwrap();
+            let row: String = actual_row.iter().collect();
+            writeln!(output, "{y:>4}   actual |{row}|").unwrap();
+            writeln!(output, "{:14}|{}", "", marker.trim_end()).unwrap();
+        }
+
+        if cells.is_empty() && expected_size == actual_size {
+            return None;
+        }
+        let plural = if cells.len() == 1 { "" } else { "s" };
+        writeln!(output, "{} different cell{plural}:", cells.len()).unwrap();
+        let describe = |x: Option<char>| x.map_or("nothing".to_string(), |x| format!("{x:?}"));
+        for &(x, y, a, b) in cells.iter().take(MAX_LISTED_CELLS) {
+            writeln!(
+                output,
+                "  ({x}, {y}): expected {}, got {}",
+                describe(a),
+                describe(b)
+            )
+            .unwrap();
+        }
+        if cells.len() > MAX_LISTED_CELLS {
+            writeln!(output, "  and {} more", cells.len() - MAX_LISTED_CELLS).unwrap();
+        }
+        Some(output)
+    }
+}
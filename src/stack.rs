@@ -0,0 +1,302 @@
+pub mod stack {
+    use std::any::Any;
+
+    use ruscii::keyboard::Key;
+
+    use crate::screen::screen::{
+        Context, Framebuffer, GameState, Modifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+
+    /// What the scene stack should do after a scene has handled something
+    pub enum Transition {
+        Stay,
+        /// Pauses the current scene and starts this one on top of it
+        Push(Box<dyn Scene>),
+        /// Ends the current scene and resumes the one below it.
+        /// Popping the only scene stops the program instead, leaving it to draw
+        /// the last frame.
+        Pop,
+        /// Ends the current scene and starts this one in its place
+        Replace(Box<dyn Scene>),
+    }
+
+    /// Lets a scene stack hand back the scenes it holds as their own types.
+    /// Call it on the scene itself rather than a box holding it.
+    pub trait AsAny {
+        fn as_any(&self) -> &dyn Any;
+        fn as_any_mut(&mut self) -> &mut dyn Any;
+    }
+
+    impl<T: Any> AsAny for T {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    /// One screen of the program, such as a menu or a viewer, which a `SceneStack` runs.
+    /// Only the scene on top of the stack updates and gets input.
+    pub trait Scene: AsAny {
+        /// Called once a frame with the seconds since the last frame
+        fn update(&mut self, dt: f32, context: &mut Context) -> Transition;
+        fn draw(&self, framebuffer: &mut Framebuffer);
+
+        fn key_pressed(&mut self, _key: Key, _context: &mut Context) -> Transition {
+            Transition::Stay
+        }
+
+        fn key_released(&mut self, _key: Key, _context: &mut Context) -> Transition {
+            Transition::Stay
+        }
+
+        fn key_down(&mut self, _key: Key, _context: &mut Context) -> Transition {
+            Transition::Stay
+        }
+
+        fn mouse(&mut self, _event: MouseEvent, _context: &mut Context) -> Transition {
+            Transition::Stay
+        }
+
+        /// Called when the window changes size, and just after the scene is entered.
+        /// Paused scenes are told too, since they can still be seen under overlays.
+        fn resize(&mut self, _width: usize, _height: usize, _context: &mut Context) {}
+
+        /// Like `GameState::fixed_update`, for the scene on top
+        fn fixed_update(&mut self, _step: f32, _context: &mut Context) {}
+
+        /// Called when the scene starts, before it's told the window's size
+        fn enter(&mut self, _context: &mut Context) {}
+
+        /// Called when the scene is popped or replaced
+        fn exit(&mut self, _context: &mut Context) {}
+
+        /// Called when another scene is pushed on top of this one
+        fn pause(&mut self, _context: &mut Context) {}
+
+        /// Called when the scene on top of this one is popped
+        fn resume(&mut self, _context: &mut Context) {}
+
+        /// Whether the scene only covers part of the window, so the scene below it
+        /// should still be drawn underneath
+        fn is_overlay(&self) -> bool {
+            false
+        }
+    }
+
+    /// A stack of scenes, which runs the one on top as a game state.
+    /// Overlays are drawn on top of whichever scenes are below them, up to and including
+    /// the first one that isn't an overlay.
+    pub struct SceneStack {
+        scenes: Vec<Box<dyn Scene>>,
+        /// Whether the first scene has been entered yet
+        started: bool,
+        size: (usize, usize),
+    }
+
+    impl SceneStack {
+        pub fn new(scene: impl Scene + 'static) -> Self {
+            Self {
+                scenes: vec![Box::new(scene)],
+                started: false,
+                size: (0, 0),
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.scenes.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.scenes.is_empty()
+        }
+
+        pub fn top(&self) -> Option<&dyn Scene> {
+            self.scenes.last().map(|x| x.as_ref())
+        }
+
+        /// The scene on top of the stack, if it's a `T`
+        pub fn top_as<T: Scene + 'static>(&self) -> Option<&T> {
+            self.top()?.as_any().downcast_ref()
+        }
+
+        pub fn top_as_mut<T: Scene + 'static>(&mut self) -> Option<&mut T> {
+            self.scenes.last_mut()?.as_mut().as_any_mut().downcast_mut()
+        }
+
+        /// The highest scene in the stack which is a `T`
+        pub fn find<T: Scene + 'static>(&self) -> Option<&T> {
+            self.scenes
+                .iter()
+                .rev()
+                .find_map(|x| x.as_ref().as_any().downcast_ref())
+        }
+
+        /// Enters the first scene, the first time the stack is used
+        fn start(&mut self, context: &mut Context) {
+            if !self.started {
+                self.started = true;
+                if let Some(scene) = self.scenes.last_mut() {
+                    scene.enter(context);
+                }
+            }
+        }
+
+        /// Sends something to the scene on top, then carries out the transition it returns
+        fn send(
+            &mut self,
+            context: &mut Context,
+            event: impl FnOnce(&mut dyn Scene, &mut Context) -> Transition,
+        ) {
+            self.start(context);
+            let Some(scene) = self.scenes.last_mut() else {
+                return;
+            };
+            let transition = event(scene.as_mut(), context);
+            self.apply(transition, context);
+        }
+
+        fn apply(&mut self, transition: Transition, context: &mut Context) {
+            match transition {
+                Transition::Stay => {}
+                Transition::Push(mut scene) => {
+                    if let Some(top) = self.scenes.last_mut() {
+                        top.pause(context);
+                    }
+                    scene.enter(context);
+                    scene.resize(self.size.0, self.size.1, context);
+                    self.scenes.push(scene);
+                }
+                Transition::Pop if self.scenes.len() <= 1 => context.stop(),
+                Transition::Pop => {
+                    if let Some(mut top) = self.scenes.pop() {
+                        top.exit(context);
+                    }
+                    if let Some(top) = self.scenes.last_mut() {
+                        top.resume(context);
+                    }
+                }
+                Transition::Replace(mut scene) => {
+                    if let Some(mut top) = self.scenes.pop() {
+                        top.exit(context);
+                    }
+                    scene.enter(context);
+                    scene.resize(self.size.0, self.size.1, context);
+                    self.scenes.push(scene);
+                }
+            }
+        }
+    }
+
+    impl GameState for SceneStack {
+        fn update(&mut self, dt: f32, context: &mut Context) {
+            self.send(context, |scene, context| scene.update(dt, context));
+        }
+
+        fn draw(&self, framebuffer: &mut Framebuffer) {
+            let bottom = self
+                .scenes
+                .iter()
+                .rposition(|x| !x.is_overlay())
+                .unwrap_or(0);
+            for (i, scene) in self.scenes.iter().enumerate().skip(bottom) {
+                if i > bottom {
+                    // overlays go in front of everything, however close it is
+                    framebuffer.clear_depth();
+                }
+                scene.draw(framebuffer);
+            }
+        }
+
+        fn key_pressed(&mut self, key: Key, context: &mut Context) {
+            self.send(context, |scene, context| scene.key_pressed(key, context));
+        }
+
+        fn key_released(&mut self, key: Key, context: &mut Context) {
+            self.send(context, |scene, context| scene.key_released(key, context));
+        }
+
+        fn key_down(&mut self, key: Key, context: &mut Context) {
+            self.send(context, |scene, context| scene.key_down(key, context));
+        }
+
+        fn resize(&mut self, width: usize, height: usize, context: &mut Context) {
+            self.start(context);
+            self.size = (width, height);
+            for scene in &mut self.scenes {
+                scene.resize(width, height, context);
+            }
+        }
+
+        fn fixed_update(&mut self, step: f32, context: &mut Context) {
+            self.start(context);
+            if let Some(scene) = self.scenes.last_mut() {
+                scene.fixed_update(step, context);
+            }
+        }
+
+        fn mouse_down(
+            &mut self,
+            button: MouseButton,
+            position: (usize, usize),
+            modifiers: Modifiers,
+            context: &mut Context,
+        ) {
+            let event = mouse_event(MouseEventKind::Down(button), position, modifiers);
+            self.send(context, |scene, context| scene.mouse(event, context));
+        }
+
+        fn mouse_up(
+            &mut self,
+            button: MouseButton,
+            position: (usize, usize),
+            modifiers: Modifiers,
+            context: &mut Context,
+        ) {
+            let event = mouse_event(MouseEventKind::Up(button), position, modifiers);
+            self.send(context, |scene, context| scene.mouse(event, context));
+        }
+
+        fn mouse_moved(
+            &mut self,
+            position: (usize, usize),
+            modifiers: Modifiers,
+            context: &mut Context,
+        ) {
+            let event = mouse_event(MouseEventKind::Moved, position, modifiers);
+            self.send(context, |scene, context| scene.mouse(event, context));
+        }
+
+        fn mouse_dragged(
+            &mut self,
+            button: MouseButton,
+            position: (usize, usize),
+            modifiers: Modifiers,
+            context: &mut Context,
+        ) {
+            let event = mouse_event(MouseEventKind::Dragged(button), position, modifiers);
+            self.send(context, |scene, context| scene.mouse(event, context));
+        }
+
+        fn mouse_scrolled(
+            &mut self,
+            amount: i32,
+            position: (usize, usize),
+            modifiers: Modifiers,
+            context: &mut Context,
+        ) {
+            let event = mouse_event(MouseEventKind::Scrolled(amount), position, modifiers);
+            self.send(context, |scene, context| scene.mouse(event, context));
+        }
+    }
+
+    fn mouse_event(
+        kind: MouseEventKind,
+        position: (usize, usize),
+        modifiers: Modifiers,
+    ) -> MouseEvent {
+        MouseEvent::new(kind, position.0, position.1).with_modifiers(modifiers)
+    }
+}
@@ -0,0 +1,250 @@
+pub mod texture {
+    use std::{fs, path::Path};
+
+    use crate::colour::colour::Colour;
+
+    #[derive(Debug)]
+    pub enum ImageError {
+        Io(std::io::Error),
+        /// The file isn't a PPM or PGM image, or is cut short
+        Format(String),
+    }
+
+    impl From<std::io::Error> for ImageError {
+        fn from(value: std::io::Error) -> Self {
+            ImageError::Io(value)
+        }
+    }
+
+    /// A grid of colours, with the top left pixel first
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Image {
+        width: usize,
+        height: usize,
+        pixels: Vec<Colour>,
+    }
+
+    impl Image {
+        /// A black image
+        pub fn new(width: usize, height: usize) -> Self {
+            Self {
+                width,
+                height,
+                pixels: vec![Colour::BLACK; width * height],
+            }
+        }
+
+        /// Fails unless there are exactly `width * height` pixels
+        #[allow(clippy::result_unit_err)]
+        pub fn from_pixels(width: usize, height: usize, pixels: Vec<Colour>) -> Result<Self, ()> {
+            if pixels.len() != width * height {
+                return Err(());
+            }
+            Ok(Self {
+                width,
+                height,
+                pixels,
+            })
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        pub fn pixels(&self) -> &[Colour] {
+            &self.pixels
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> Colour {
+            self.pixels[y * self.width + x]
+        }
+
+        pub fn set(&mut self, x: usize, y: usize, colour: Colour) {
+            self.pixels[y * self.width + x] = colour;
+        }
+
+        pub fn load(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+            Self::parse(&fs::read(path)?)
+        }
+
+        pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ImageError> {
+            Ok(fs::write(path, self.to_ppm())?)
+        }
+
+        /// Encodes the image as a binary (P6) PPM file, clamping each colour
+        pub fn to_ppm(&self) -> Vec<u8> {
+            let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+            bytes.extend(self.pixels.iter().flat_map(|x| x.to_rgb8()));
+            bytes
+        }
+
+        /// Reads a binary (P5, P6) or ASCII (P2, P3) PGM or PPM image
+        pub fn parse(bytes: &[u8]) -> Result<Self, ImageError> {
+            let format = |message: &str| ImageError::Format(message.to_string());
+            let mut position = 0;
+            let mut header = Vec::with_capacity(4);
+            while header.len() < 4 {
+                header.push(next_token(bytes, &mut position).ok_or(format("header is cut short"))?);
+            }
+            let number = |token: &[u8]| -> Result<usize, ImageError> {
+                std::str::from_utf8(token)
+                    .ok()
+                    .and_then(|x| x.parse().ok())
+                    .ok_or(format("header contains something other than a number"))
+            };
+            let (width, height, max) = (number(header[1])?, number(header[2])?, number(header[3])?);
+            if max == 0 || max > u16::MAX as usize {
+                return Err(format("maximum value must be from 1 to 65535"));
+            }
+            let (channels, binary) = match header[0] {
+                b"P2" => (1, false),
+                b"P3" => (3, false),
+                b"P5" => (1, true),
+                b"P6" => (3, true),
+                _ => return Err(format("only P2, P3, P5 and P6 images are supported")),
+            };
+
+            let count = width
+                .checked_mul(height)
+                .and_then(|x| x.checked_mul(channels))
+                .ok_or(format("image is too large"))?;
+            let samples: Vec<usize> = if binary {
+                // exactly one whitespace character separates the header from the pixels
+                let data = bytes.get(position + 1..).unwrap_or_default();
+                let size = if max < 256 { 1 } else { 2 };
+                if count.checked_mul(size).is_none_or(|x| data.len() < x) {
+                    return Err(format("pixel data is cut short"));
+                }
+                data.chunks_exact(size)
+                    .take(count)
+                    .map(|x| x.iter().fold(0, |acc, &byte| acc * 256 + byte as usize))
+                    .collect()
+            } else {
+                // the header can claim any size, so only reserve what the file could hold
+                let mut samples = Vec::with_capacity(count.min(bytes.len()));
+                while samples.len() < count {
+                    let token = next_token(bytes, &mut position)
+                        .ok_or(format("pixel data is cut short"))?;
+                    samples.push(number(token)?);
+                }
+                samples
+            };
+
+            let value = |x: usize| x.min(max) as f32 / max as f32;
+            let pixels = samples
+                .chunks_exact(channels)
+                .map(|x| match x {
+                    &[grey] => Colour::grey(value(grey)),
+                    _ => Colour::new(value(x[0]), value(x[1]), value(x[2])),
+                })
+                .collect();
+            Ok(Self {
+                width,
+                height,
+                pixels,
+            })
+        }
+    }
+
+    /// The next whitespace-separated token in a PNM header, skipping `#` comments.
+    /// Leaves `position` on the character just after the token.
+    fn next_token<'a>(bytes: &'a [u8], position: &mut usize) -> Option<&'a [u8]> {
+        loop {
+            match bytes.get(*position)? {
+                b'#' => {
+                    while bytes.get(*position).is_some_and(|&x| x != b'\n') {
+                        *position += 1;
+                    }
+                }
+                x if x.is_ascii_whitespace() => *position += 1,
+                _ => break,
+            }
+        }
+        let start = *position;
+        while bytes
+            .get(*position)
+            .is_some_and(|x| !x.is_ascii_whitespace())
+        {
+            *position += 1;
+        }
+        Some(&bytes[start..*position])
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Filter {
+        Nearest,
+        /// Blends the four closest pixels
+        Bilinear,
+    }
+
+    /// What happens to texture coordinates outside 0 to 1
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Wrap {
+        Repeat,
+        /// Use the closest edge pixel
+        Clamp,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Texture {
+        image: Image,
+        pub filter: Filter,
+        pub wrap: Wrap,
+    }
+
+    impl Texture {
+        pub fn new(image: Image) -> Self {
+            Self {
+                image,
+                filter: Filter::Bilinear,
+                wrap: Wrap::Repeat,
+            }
+        }
+
+        pub fn load(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+            Ok(Self::new(Image::load(path)?))
+        }
+
+        pub fn image(&self) -> &Image {
+            &self.image
+        }
+
+        /// The colour at texture coordinates `(u, v)`,
+        /// where `(0, 0)` is the bottom left and `(1, 1)` is the top right
+        pub fn sample(&self, u: f32, v: f32) -> Colour {
+            let (width, height) = (self.image.width, self.image.height);
+            if width == 0 || height == 0 {
+                return Colour::WHITE;
+            }
+            // in pixels, where pixel centres are at whole numbers
+            let x = u * width as f32 - 0.5;
+            let y = (1.0 - v) * height as f32 - 0.5;
+            match self.filter {
+                Filter::Nearest => self.texel(x.round() as i64, y.round() as i64),
+                Filter::Bilinear => {
+                    let (left, top) = (x.floor(), y.floor());
+                    let (tx, ty) = (x - left, y - top);
+                    let (left, top) = (left as i64, top as i64);
+                    let upper = self.texel(left, top).lerp(self.texel(left + 1, top), tx);
+                    let lower = self
+                        .texel(left, top + 1)
+                        .lerp(self.texel(left + 1, top + 1), tx);
+                    upper.lerp(lower, ty)
+                }
+            }
+        }
+
+        fn texel(&self, x: i64, y: i64) -> Colour {
+            let (width, height) = (self.image.width as i64, self.image.height as i64);
+            let (x, y) = match self.wrap {
+                Wrap::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+                Wrap::Clamp => (x.clamp(0, width - 1), y.clamp(0, height - 1)),
+            };
+            self.image.get(x as usize, y as usize)
+        }
+    }
+}